@@ -0,0 +1,133 @@
+//! One concurrency budget for caption inference across all AI backends. Every request to LM Studio
+//! or Ollama (single or batch) holds a permit while it runs, so two batches started from different
+//! backends can't together exceed the limit. The limiter lives in Tauri managed state.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::State;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+const DEFAULT_AI_CONCURRENCY: u32 = 3;
+/// Same cap as a single batch's `concurrency`.
+const MAX_AI_CONCURRENCY: u32 = 8;
+
+struct LimitState {
+    /// Limit reported to the frontend.
+    limit: u32,
+    /// Permits still out with running requests that must be forgotten, not returned, when they
+    /// are released, because the limit was lowered below the number in use.
+    retire: u32,
+}
+
+pub struct AiConcurrencyLimiter {
+    semaphore: Semaphore,
+    /// Held while resizing and while releasing a permit, so both see a consistent `retire`.
+    state: Mutex<LimitState>,
+}
+
+impl Default for AiConcurrencyLimiter {
+    fn default() -> Self {
+        Self {
+            semaphore: Semaphore::new(DEFAULT_AI_CONCURRENCY as usize),
+            state: Mutex::new(LimitState {
+                limit: DEFAULT_AI_CONCURRENCY,
+                retire: 0,
+            }),
+        }
+    }
+}
+
+/// A held inference slot. Dropping it returns the slot to the pool, or retires it if the limit
+/// was lowered while it was in use.
+pub struct InferencePermit<'a> {
+    permit: Option<SemaphorePermit<'a>>,
+    limiter: &'a AiConcurrencyLimiter,
+}
+
+impl Drop for InferencePermit<'_> {
+    fn drop(&mut self) {
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+        let mut state = match self.limiter.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if state.retire > 0 {
+            state.retire -= 1;
+            permit.forget();
+        } else {
+            // Released under the lock so a concurrent resize sees it as available.
+            drop(permit);
+        }
+    }
+}
+
+impl AiConcurrencyLimiter {
+    /// Wait for a free inference slot. The slot is released when the permit is dropped.
+    pub async fn acquire(&self) -> Result<InferencePermit<'_>, String> {
+        let permit = self.semaphore.acquire().await.map_err(|e| e.to_string())?;
+        Ok(InferencePermit {
+            permit: Some(permit),
+            limiter: self,
+        })
+    }
+
+    fn set_limit(&self, new_limit: u32) -> Result<u32, String> {
+        let new_limit = new_limit.clamp(1, MAX_AI_CONCURRENCY);
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        if new_limit > state.limit {
+            // Slots still waiting to be retired are simply kept; only the rest are new.
+            let mut added = new_limit - state.limit;
+            let kept = added.min(state.retire);
+            state.retire -= kept;
+            added -= kept;
+            self.semaphore.add_permits(added as usize);
+        } else if new_limit < state.limit {
+            let excess = (state.limit - new_limit) as usize;
+            let forgotten = self.semaphore.forget_permits(excess);
+            state.retire += (excess - forgotten) as u32;
+        }
+        state.limit = new_limit;
+        Ok(new_limit)
+    }
+
+    fn usage(&self) -> Result<AiConcurrency, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        let available = self.semaphore.available_permits() as u32;
+        // Permits in use = everything issued (limit plus those awaiting retirement) not available.
+        Ok(AiConcurrency {
+            limit: state.limit,
+            in_use: (state.limit + state.retire).saturating_sub(available),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AiConcurrency {
+    pub limit: u32,
+    /// Requests currently holding a slot.
+    pub in_use: u32,
+}
+
+#[tauri::command]
+pub fn get_ai_concurrency(
+    limiter: State<'_, AiConcurrencyLimiter>,
+) -> Result<AiConcurrency, String> {
+    limiter.usage()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetAiConcurrencyPayload {
+    pub limit: u32,
+}
+
+/// Set the system-wide limit (1-8). Lowering it never interrupts running requests: slots are
+/// taken out of the pool as they are released. Returns the applied limit.
+#[tauri::command]
+pub fn set_ai_concurrency(
+    limiter: State<'_, AiConcurrencyLimiter>,
+    payload: SetAiConcurrencyPayload,
+) -> Result<u32, String> {
+    limiter.set_limit(payload.limit)
+}
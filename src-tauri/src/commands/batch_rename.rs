@@ -1,13 +1,67 @@
-//! Batch rename image files (and their .txt caption files) with a prefix and sequential index.
+//! Batch rename image files (and their caption files) with a prefix and sequential index,
+//! or move them into a project subfolder.
 
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::Emitter;
 
-fn caption_path_for(image_path: &Path) -> PathBuf {
-    image_path.with_extension("txt")
+use super::metadata_store;
+use super::project_config::caption_extension;
+use super::ratings::{load_ratings, save_ratings, RatingsData};
+
+/// Number of batches kept in rename_history.json.
+const MAX_RENAME_HISTORY: usize = 20;
+
+fn caption_path_for(image_path: &Path, caption_ext: &str) -> PathBuf {
+    image_path.with_extension(caption_ext)
+}
+
+/// One renamed image in a batch, as recorded for undo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RenameRecord {
+    old_relative: String,
+    new_relative: String,
+    had_caption: bool,
+    /// Modification time (ms since epoch) of the renamed file; undo skips files changed since.
+    modified_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RenameBatch {
+    timestamp: u64,
+    renames: Vec<RenameRecord>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RenameHistory {
+    batches: Vec<RenameBatch>,
+}
+
+fn rename_history_path(root: &Path) -> PathBuf {
+    root.join(".lora-studio").join("rename_history.json")
+}
+
+fn load_rename_history(root: &Path) -> RenameHistory {
+    metadata_store::read_to_string(&rename_history_path(root))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_rename_history(root: &Path, history: &RenameHistory) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(history).map_err(|e| e.to_string())?;
+    metadata_store::write_atomic(&rename_history_path(root), &content)
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,6 +75,20 @@ pub struct BatchRenamePayload {
     pub start_index: u32,
     /// Zero-pad index to this many digits (e.g. 4 -> 0001, 0002).
     pub zero_pad: u32,
+    /// Compute the mappings and run all checks without renaming or touching metadata.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Filename pattern with tokens {prefix}, {index} (zero-padded), {orig} (original stem),
+    /// {parent} (parent folder name), and {ext}. If {ext} is absent the original extension is
+    /// appended. Defaults to "{prefix}_{index}".
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenameMapping {
+    pub old_relative: String,
+    pub new_relative: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -28,6 +96,8 @@ pub struct BatchRenameResult {
     pub success: bool,
     pub renamed_count: u32,
     pub errors: Vec<String>,
+    /// Old -> new relative paths that were (or, for a dry run, would be) renamed.
+    pub mappings: Vec<RenameMapping>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -38,10 +108,11 @@ pub struct BatchRenameProgress {
 }
 
 fn load_json_map(path: &Path) -> Result<HashMap<String, String>, String> {
-    if !path.exists() {
-        return Ok(HashMap::new());
-    }
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let content = match metadata_store::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.to_string()),
+    };
     let data: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
     if let Some(obj) = data.as_object() {
         if let Some(map_val) = obj.get("ratings").or_else(|| obj.get("statuses")) {
@@ -60,9 +131,6 @@ fn load_json_map(path: &Path) -> Result<HashMap<String, String>, String> {
 }
 
 fn save_json_map(path: &Path, map: &HashMap<String, String>, key: &str) -> Result<(), String> {
-    let parent = path.parent().ok_or("No parent directory")?;
-    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    
     let mut obj = serde_json::Map::new();
     let mut inner = serde_json::Map::new();
     for (k, v) in map {
@@ -71,7 +139,92 @@ fn save_json_map(path: &Path, map: &HashMap<String, String>, key: &str) -> Resul
     obj.insert(key.to_string(), serde_json::Value::Object(inner));
     
     let content = serde_json::to_string_pretty(&obj).map_err(|e| e.to_string())?;
-    fs::write(path, content).map_err(|e| e.to_string())
+    metadata_store::write_atomic(path, &content)
+}
+
+/// Replace characters that are invalid in filenames on any platform, and trim trailing dots/spaces.
+fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    cleaned.trim().trim_end_matches(['.', ' ']).to_string()
+}
+
+/// Build the new filename for one image from the rename pattern.
+fn render_rename_pattern(
+    pattern: &str,
+    prefix: &str,
+    index: u32,
+    zero_pad: usize,
+    old_path: &Path,
+    ext: &str,
+) -> String {
+    let orig = old_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let parent = old_path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .unwrap_or("");
+    let name = pattern
+        .replace("{prefix}", prefix)
+        .replace("{index}", &format!("{:0width$}", index, width = zero_pad))
+        .replace("{orig}", orig)
+        .replace("{parent}", parent);
+    let name = if name.contains("{ext}") {
+        name.replace("{ext}", ext)
+    } else {
+        format!("{}.{}", name, ext)
+    };
+    sanitize_file_name(&name)
+}
+
+/// Move ratings, star ratings, and crop_status entries from old to new relative paths.
+fn remap_metadata(root_path: &str, crop_status_path: &Path, mappings: &[(String, String)]) {
+    let mut ratings = load_ratings(root_path);
+    let mut crop_statuses = load_json_map(crop_status_path).unwrap_or_default();
+
+    let mut updated_ratings = HashMap::new();
+    let mut updated_stars = HashMap::new();
+    for (old_path, new_path) in mappings {
+        if let Some(rating) = ratings.ratings.remove(old_path) {
+            updated_ratings.insert(new_path.clone(), rating);
+        }
+        if let Some(stars) = ratings.stars.remove(old_path) {
+            updated_stars.insert(new_path.clone(), stars);
+        }
+    }
+    // Keep any ratings for files that weren't renamed
+    updated_ratings.extend(ratings.ratings);
+    updated_stars.extend(ratings.stars);
+
+    let updated = RatingsData {
+        ratings: updated_ratings,
+        stars: updated_stars,
+    };
+    if let Err(e) = save_ratings(root_path, &updated) {
+        eprintln!("Warning: Failed to update ratings file: {}", e);
+    }
+    
+    // Update crop_status file with new paths
+    let mut updated_crop_statuses = HashMap::new();
+    for (old_path, new_path) in mappings {
+        if let Some(status) = crop_statuses.remove(old_path) {
+            updated_crop_statuses.insert(new_path.clone(), status);
+        }
+    }
+    // Keep any statuses for files that weren't renamed
+    for (k, v) in crop_statuses {
+        updated_crop_statuses.insert(k, v);
+    }
+    
+    if let Err(e) = save_json_map(crop_status_path, &updated_crop_statuses, "statuses") {
+        eprintln!("Warning: Failed to update crop_status file: {}", e);
+    }
 }
 
 /// Renames image files and their caption files with prefix + zero-padded index.
@@ -90,7 +243,12 @@ pub fn batch_rename(
     let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
 
     let prefix = payload.prefix.trim();
-    if prefix.is_empty() {
+    let pattern = payload
+        .pattern
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+    if pattern.is_none_or(|p| p.contains("{prefix}")) && prefix.is_empty() {
         return Err("Prefix cannot be empty".to_string());
     }
 
@@ -99,14 +257,19 @@ pub fn batch_rename(
     let mut errors = Vec::new();
     let mut renamed = 0u32;
     
-    // Load ratings and crop status files
-    let ratings_path = root.join(".lora-studio").join("ratings.json");
     let crop_status_path = root.join(".lora-studio").join("crop_status.json");
-    let mut ratings = load_json_map(&ratings_path).unwrap_or_default();
-    let mut crop_statuses = load_json_map(&crop_status_path).unwrap_or_default();
+    let caption_ext = caption_extension(&root);
     
-    // Track path mappings for updating metadata
-    let mut path_mappings: Vec<(String, String)> = Vec::new();
+    // Track path mappings for updating metadata and the undo history
+    let mut path_mappings: Vec<RenameRecord> = Vec::new();
+
+    // A dry run moves nothing, so track what the real run would have vacated and claimed
+    // to report the same collisions. Both stay empty for a real run.
+    let mut vacated: HashSet<PathBuf> = HashSet::new();
+    let mut claimed: HashSet<PathBuf> = HashSet::new();
+    let is_taken = |p: &Path, vacated: &HashSet<PathBuf>, claimed: &HashSet<PathBuf>| {
+        (p.exists() && !vacated.contains(p)) || claimed.contains(p)
+    };
     
     let total = payload.relative_paths.len() as u32;
     let mut current = 0u32;
@@ -152,7 +315,15 @@ pub fn batch_rename(
             .and_then(|e| e.to_str())
             .unwrap_or("png")
             .to_string();
-        let new_name = format!("{}_{:0width$}.{}", prefix, index, ext, width = zero_pad as usize);
+        let new_name = match pattern {
+            Some(pattern) => render_rename_pattern(pattern, prefix, index, zero_pad as usize, &old_path, &ext),
+            None => format!("{}_{:0width$}.{}", prefix, index, ext, width = zero_pad as usize),
+        };
+        if new_name.is_empty() || new_name.starts_with('.') {
+            errors.push(format!("Pattern produced an invalid name for: {}", relative_path));
+            index += 1;
+            continue;
+        }
         let parent = old_path.parent().unwrap_or(&root);
         let new_path = parent.join(&new_name);
 
@@ -162,22 +333,52 @@ pub fn batch_rename(
             continue;
         }
 
-        if new_path.exists() {
+        if is_taken(&new_path, &vacated, &claimed) {
             errors.push(format!("Target already exists: {}", new_name));
             index += 1;
             continue;
         }
 
+        let new_relative = new_path.strip_prefix(&root)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| new_name.clone());
+
+        if payload.dry_run {
+            let caption_old = caption_path_for(&old_path, &caption_ext);
+            let caption_new = caption_path_for(&new_path, &caption_ext);
+            let had_caption = caption_old.exists();
+            if had_caption && is_taken(&caption_new, &vacated, &claimed) {
+                errors.push(format!("Caption target exists: {}", new_name));
+            } else {
+                renamed += 1;
+                vacated.insert(old_path.clone());
+                claimed.insert(new_path.clone());
+                if had_caption {
+                    vacated.insert(caption_old);
+                    claimed.insert(caption_new);
+                }
+                path_mappings.push(RenameRecord {
+                    old_relative: relative_path.clone(),
+                    new_relative,
+                    had_caption,
+                    modified_ms: 0,
+                });
+            }
+            index += 1;
+            continue;
+        }
+
         if let Err(e) = fs::rename(&old_path, &new_path) {
             errors.push(format!("Rename {}: {}", relative_path, e));
             index += 1;
             continue;
         }
 
-        let caption_old = caption_path_for(&old_path);
-        let caption_new = new_path.with_extension("txt");
+        let caption_old = caption_path_for(&old_path, &caption_ext);
+        let caption_new = caption_path_for(&new_path, &caption_ext);
+        let had_caption = caption_old.exists();
         let mut ok = true;
-        if caption_old.exists() {
+        if had_caption {
             if caption_new.exists() {
                 let _ = fs::rename(&new_path, &old_path);
                 errors.push(format!("Caption target exists: {}", new_name));
@@ -191,51 +392,275 @@ pub fn batch_rename(
         if ok {
             renamed += 1;
             // Track the path mapping for metadata updates
-            let new_relative = new_path.strip_prefix(&root)
-                .map(|p| p.to_string_lossy().replace('\\', "/"))
-                .unwrap_or_else(|_| new_name.clone());
-            path_mappings.push((relative_path.clone(), new_relative));
+            path_mappings.push(RenameRecord {
+                old_relative: relative_path.clone(),
+                new_relative,
+                had_caption,
+                modified_ms: modified_ms(&new_path).unwrap_or(0),
+            });
         }
         index += 1;
     }
     
-    // Update ratings file with new paths
-    if !path_mappings.is_empty() {
-        let mut updated_ratings = HashMap::new();
-        for (old_path, new_path) in &path_mappings {
-            if let Some(rating) = ratings.remove(old_path) {
-                updated_ratings.insert(new_path.clone(), rating);
-            }
+    let mappings: Vec<RenameMapping> = path_mappings
+        .iter()
+        .map(|r| RenameMapping {
+            old_relative: r.old_relative.clone(),
+            new_relative: r.new_relative.clone(),
+        })
+        .collect();
+
+    // Update ratings and crop_status files with new paths, and record the batch for undo
+    if !payload.dry_run && !path_mappings.is_empty() {
+        let pairs: Vec<(String, String)> = mappings
+            .iter()
+            .map(|m| (m.old_relative.clone(), m.new_relative.clone()))
+            .collect();
+        remap_metadata(&payload.root_path, &crop_status_path, &pairs);
+
+        let mut history = load_rename_history(&root);
+        history.batches.push(RenameBatch {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            renames: path_mappings,
+        });
+        let excess = history.batches.len().saturating_sub(MAX_RENAME_HISTORY);
+        history.batches.drain(..excess);
+        if let Err(e) = save_rename_history(&root, &history) {
+            eprintln!("Warning: Failed to update rename history: {}", e);
+        }
+    }
+
+    Ok(BatchRenameResult {
+        success: errors.is_empty(),
+        renamed_count: renamed,
+        errors,
+        mappings,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UndoLastRenamePayload {
+    pub root_path: String,
+}
+
+/// Reverse the most recent batch_rename: rename images and captions back and move the
+/// ratings/crop_status entries back to the old paths. Files modified since the rename, or whose
+/// old name is taken again, are left alone and reported in errors; they stay in the history as
+/// the last batch so the undo can be retried once the conflict is resolved.
+#[tauri::command]
+pub fn undo_last_rename(payload: UndoLastRenamePayload) -> Result<BatchRenameResult, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.exists() || !root.is_dir() {
+        return Err("Root path does not exist or is not a directory".to_string());
+    }
+
+    let mut history = load_rename_history(&root);
+    let batch = history
+        .batches
+        .pop()
+        .ok_or_else(|| "No batch rename to undo".to_string())?;
+    let caption_ext = caption_extension(&root);
+
+    let mut errors = Vec::new();
+    let mut reverted = 0u32;
+    let mut mappings: Vec<(String, String)> = Vec::new();
+    let mut remaining: Vec<RenameRecord> = Vec::new();
+
+    for record in batch.renames.iter().rev() {
+        let new_path = root.join(record.new_relative.replace('/', std::path::MAIN_SEPARATOR_STR));
+        let old_path = root.join(record.old_relative.replace('/', std::path::MAIN_SEPARATOR_STR));
+
+        if !new_path.is_file() {
+            errors.push(format!("Not found: {}", record.new_relative));
+            remaining.push(record.clone());
+            continue;
         }
-        // Keep any ratings for files that weren't renamed
-        for (k, v) in ratings {
-            updated_ratings.insert(k, v);
+        if modified_ms(&new_path) != Some(record.modified_ms) {
+            errors.push(format!("Modified since rename, skipped: {}", record.new_relative));
+            remaining.push(record.clone());
+            continue;
         }
-        
-        if let Err(e) = save_json_map(&ratings_path, &updated_ratings, "ratings") {
-            eprintln!("Warning: Failed to update ratings file: {}", e);
+        let caption_new = caption_path_for(&new_path, &caption_ext);
+        let caption_old = caption_path_for(&old_path, &caption_ext);
+        if old_path.exists() || (record.had_caption && caption_old.exists()) {
+            errors.push(format!("Original name is taken: {}", record.old_relative));
+            remaining.push(record.clone());
+            continue;
         }
-        
-        // Update crop_status file with new paths
-        let mut updated_crop_statuses = HashMap::new();
-        for (old_path, new_path) in &path_mappings {
-            if let Some(status) = crop_statuses.remove(old_path) {
-                updated_crop_statuses.insert(new_path.clone(), status);
+
+        if let Err(e) = fs::rename(&new_path, &old_path) {
+            errors.push(format!("Rename {}: {}", record.new_relative, e));
+            remaining.push(record.clone());
+            continue;
+        }
+        if record.had_caption && caption_new.exists() && fs::rename(&caption_new, &caption_old).is_err() {
+            let _ = fs::rename(&old_path, &new_path);
+            errors.push(format!("Failed to restore caption for: {}", record.new_relative));
+            remaining.push(record.clone());
+            continue;
+        }
+        reverted += 1;
+        mappings.push((record.new_relative.clone(), record.old_relative.clone()));
+    }
+
+    if !mappings.is_empty() {
+        let crop_status_path = root.join(".lora-studio").join("crop_status.json");
+        remap_metadata(&payload.root_path, &crop_status_path, &mappings);
+    }
+    if !remaining.is_empty() {
+        remaining.reverse();
+        history.batches.push(RenameBatch {
+            timestamp: batch.timestamp,
+            renames: remaining,
+        });
+    }
+    save_rename_history(&root, &history)?;
+
+    Ok(BatchRenameResult {
+        success: errors.is_empty(),
+        renamed_count: reverted,
+        errors,
+        mappings: mappings
+            .into_iter()
+            .map(|(old_relative, new_relative)| RenameMapping {
+                old_relative,
+                new_relative,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MoveImagesPayload {
+    pub root_path: String,
+    /// Relative paths of images to move (from project root).
+    pub relative_paths: Vec<String>,
+    /// Destination folder relative to the project root (e.g. "_review"); created if needed.
+    pub dest_subfolder: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveImagesResult {
+    pub success: bool,
+    pub moved_count: u32,
+    pub errors: Vec<String>,
+    /// Old -> new relative paths of the moved images.
+    pub mappings: Vec<RenameMapping>,
+}
+
+/// Resolve `dest_subfolder` to a folder inside the project, rejecting absolute paths, `..`,
+/// and the .lora-studio metadata folder.
+fn resolve_dest_subfolder(root: &Path, canonical_root: &Path, dest_subfolder: &str) -> Result<PathBuf, String> {
+    let rel = Path::new(dest_subfolder.trim().trim_matches(['/', '\\']));
+    let valid = !rel.as_os_str().is_empty()
+        && rel.components().all(|c| match c {
+            Component::Normal(name) => name != ".lora-studio",
+            _ => false,
+        });
+    if !valid {
+        return Err(format!("Invalid destination folder: {}", dest_subfolder));
+    }
+    let dest = root.join(rel);
+    fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+    // Catch symlinked folders that lead outside the project
+    let canonical_dest = dest.canonicalize().map_err(|e| e.to_string())?;
+    if canonical_dest.strip_prefix(canonical_root).is_err() {
+        return Err(format!("Destination outside project: {}", dest_subfolder));
+    }
+    Ok(dest)
+}
+
+/// Move images and their caption files into a subfolder of the project (e.g. a review bucket),
+/// carrying ratings and crop_status entries over to the new relative paths.
+/// Rejects any path that resolves outside the project root.
+#[tauri::command]
+pub fn move_images(payload: MoveImagesPayload) -> Result<MoveImagesResult, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.exists() || !root.is_dir() {
+        return Err("Root path does not exist or is not a directory".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let dest_dir = resolve_dest_subfolder(&root, &canonical_root, &payload.dest_subfolder)?;
+    let caption_ext = caption_extension(&root);
+
+    let mut errors = Vec::new();
+    let mut mappings: Vec<RenameMapping> = Vec::new();
+
+    for relative_path in &payload.relative_paths {
+        let rel_normalized = relative_path.replace('/', std::path::MAIN_SEPARATOR_STR);
+        let old_path = root.join(&rel_normalized);
+        if !old_path.is_file() {
+            errors.push(format!("Not found: {}", relative_path));
+            continue;
+        }
+        match old_path.canonicalize() {
+            Ok(p) if p.strip_prefix(&canonical_root).is_ok() => {}
+            Ok(_) => {
+                errors.push(format!("Path outside project: {}", relative_path));
+                continue;
+            }
+            Err(e) => {
+                errors.push(format!("Invalid path {}: {}", relative_path, e));
+                continue;
             }
         }
-        // Keep any statuses for files that weren't renamed
-        for (k, v) in crop_statuses {
-            updated_crop_statuses.insert(k, v);
+
+        let Some(file_name) = old_path.file_name() else {
+            errors.push(format!("Invalid path: {}", relative_path));
+            continue;
+        };
+        let new_path = dest_dir.join(file_name);
+        if new_path == old_path {
+            continue;
         }
-        
-        if let Err(e) = save_json_map(&crop_status_path, &updated_crop_statuses, "statuses") {
-            eprintln!("Warning: Failed to update crop_status file: {}", e);
+        if new_path.exists() {
+            errors.push(format!("Target already exists: {}", new_path.display()));
+            continue;
+        }
+        let caption_old = caption_path_for(&old_path, &caption_ext);
+        let caption_new = caption_path_for(&new_path, &caption_ext);
+        let had_caption = caption_old.exists();
+        if had_caption && caption_new.exists() {
+            errors.push(format!("Caption target exists: {}", caption_new.display()));
+            continue;
         }
+
+        if let Err(e) = fs::rename(&old_path, &new_path) {
+            errors.push(format!("Move {}: {}", relative_path, e));
+            continue;
+        }
+        if had_caption && fs::rename(&caption_old, &caption_new).is_err() {
+            let _ = fs::rename(&new_path, &old_path);
+            errors.push(format!("Failed to move caption for: {}", relative_path));
+            continue;
+        }
+
+        let new_relative = new_path
+            .strip_prefix(&root)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| new_path.to_string_lossy().into_owned());
+        mappings.push(RenameMapping {
+            old_relative: relative_path.clone(),
+            new_relative,
+        });
     }
 
-    Ok(BatchRenameResult {
+    if !mappings.is_empty() {
+        let pairs: Vec<(String, String)> = mappings
+            .iter()
+            .map(|m| (m.old_relative.clone(), m.new_relative.clone()))
+            .collect();
+        let crop_status_path = root.join(".lora-studio").join("crop_status.json");
+        remap_metadata(&payload.root_path, &crop_status_path, &pairs);
+    }
+
+    Ok(MoveImagesResult {
         success: errors.is_empty(),
-        renamed_count: renamed,
+        moved_count: mappings.len() as u32,
         errors,
+        mappings,
     })
 }
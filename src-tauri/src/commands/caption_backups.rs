@@ -0,0 +1,177 @@
+//! Opt-in caption backups: before a caption is overwritten, the old content is copied to
+//! `.lora-studio/caption_backups/<relative image path>.<timestamp ms>.txt`, keeping the newest N per image.
+//! Enabled per project via `caption_backups` in `.lora-studio/config.json`.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::captions::{decode_caption_bytes, read_caption_text};
+use super::project_config::{
+    caption_extension_for_image, load_project_config, project_root_for_image, ProjectConfig,
+    ProjectLookup,
+};
+
+const BACKUP_EXTENSION: &str = "txt";
+
+/// Backup settings for one project; only constructed when backups are enabled.
+pub struct CaptionBackups {
+    root: PathBuf,
+    max_versions: usize,
+}
+
+impl CaptionBackups {
+    /// Backups for the project at `root`, or None if disabled.
+    pub fn for_root(root: &Path) -> Option<Self> {
+        Self::from_config(root, &load_project_config(root))
+    }
+
+    fn from_config(root: &Path, config: &ProjectConfig) -> Option<Self> {
+        config.caption_backups.then(|| Self {
+            root: root.to_path_buf(),
+            max_versions: config.max_caption_backups,
+        })
+    }
+
+    /// Backups for the project containing `image_path`, or None if disabled or not in a project.
+    pub fn for_image(image_path: &Path) -> Option<Self> {
+        project_root_for_image(image_path).and_then(|root| Self::for_root(&root))
+    }
+
+    /// Like for_image, resolving the project through a shared lookup.
+    pub fn for_image_in(lookup: &ProjectLookup, image_path: &Path) -> Option<Self> {
+        let (root, config) = lookup.project(image_path)?;
+        Self::from_config(&root, &config)
+    }
+
+    /// Folder holding the backups for `image_path`, and the file name prefix of its versions.
+    fn location(&self, image_path: &Path) -> Option<(PathBuf, String)> {
+        let rel = image_path.strip_prefix(&self.root).ok()?;
+        let file_name = rel.file_name()?.to_str()?.to_string();
+        let mut dir = self.root.join(".lora-studio").join("caption_backups");
+        if let Some(parent) = rel.parent() {
+            dir.push(parent);
+        }
+        Some((dir, format!("{}.", file_name)))
+    }
+
+    /// Existing versions for an image as (timestamp ms, path), newest first.
+    fn versions(&self, image_path: &Path) -> Vec<(u64, PathBuf)> {
+        let Some((dir, prefix)) = self.location(image_path) else {
+            return Vec::new();
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+        let suffix = format!(".{}", BACKUP_EXTENSION);
+        let mut versions: Vec<(u64, PathBuf)> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_str()?.to_string();
+                let stamp = name.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+                Some((stamp.parse().ok()?, e.path()))
+            })
+            .collect();
+        versions.sort_by_key(|v| std::cmp::Reverse(v.0));
+        versions
+    }
+
+    /// Copy the current caption (if it exists and is non-empty) byte for byte before it gets
+    /// overwritten, whatever its encoding, then drop versions beyond the limit. Failures are
+    /// ignored so they never block the edit.
+    pub fn save(&self, image_path: &Path, caption_path: &Path) {
+        let Ok(bytes) = fs::read(caption_path) else {
+            return;
+        };
+        if decode_caption_bytes(&bytes).0.trim().is_empty() {
+            return;
+        }
+        let Some((dir, prefix)) = self.location(image_path) else {
+            return;
+        };
+        if fs::create_dir_all(&dir).is_err() {
+            return;
+        }
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let backup = dir.join(format!("{}{}.{}", prefix, stamp, BACKUP_EXTENSION));
+        if fs::write(&backup, &bytes).is_err() {
+            return;
+        }
+        for (_, old) in self.versions(image_path).into_iter().skip(self.max_versions) {
+            let _ = fs::remove_file(old);
+        }
+    }
+}
+
+/// Back up an image's caption before overwriting it, if its project has backups enabled.
+pub fn backup_caption(lookup: &ProjectLookup, image_path: &Path, caption_path: &Path) {
+    if let Some(backups) = CaptionBackups::for_image_in(lookup, image_path) {
+        backups.save(image_path, caption_path);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListCaptionBackupsPayload {
+    /// Image path.
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CaptionBackup {
+    pub backup_path: String,
+    pub timestamp_ms: u64,
+    pub content: String,
+}
+
+/// List backups of an image's caption, newest first.
+#[tauri::command]
+pub fn list_caption_backups(payload: ListCaptionBackupsPayload) -> Result<Vec<CaptionBackup>, String> {
+    let image_path = PathBuf::from(&payload.path);
+    let Some(backups) = CaptionBackups::for_image(&image_path) else {
+        return Ok(Vec::new());
+    };
+    Ok(backups
+        .versions(&image_path)
+        .into_iter()
+        .map(|(timestamp_ms, path)| CaptionBackup {
+            content: read_caption_text(&path).unwrap_or_default(),
+            backup_path: path.to_string_lossy().into_owned(),
+            timestamp_ms,
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreCaptionBackupPayload {
+    /// Image path.
+    pub path: String,
+    /// A backup_path returned by list_caption_backups.
+    pub backup_path: String,
+}
+
+/// Restore a caption from a backup. The current caption is backed up first, so a restore can be undone.
+#[tauri::command]
+pub fn restore_caption_backup(payload: RestoreCaptionBackupPayload) -> Result<String, String> {
+    let image_path = PathBuf::from(&payload.path);
+    let backups = CaptionBackups::for_image(&image_path)
+        .ok_or_else(|| "Caption backups are not enabled for this project".to_string())?;
+    let backup_path = PathBuf::from(&payload.backup_path);
+    if !backups
+        .versions(&image_path)
+        .iter()
+        .any(|(_, p)| *p == backup_path)
+    {
+        return Err("Backup not found for this image".to_string());
+    }
+
+    // Backups keep the caption's original bytes, which may be UTF-16 or Windows-1252.
+    let content = read_caption_text(&backup_path).map_err(|e| e.to_string())?;
+    let caption_path = image_path.with_extension(caption_extension_for_image(&image_path));
+    backups.save(&image_path, &caption_path);
+    fs::write(&caption_path, content.trim()).map_err(|e| e.to_string())?;
+    Ok(content.trim().to_string())
+}
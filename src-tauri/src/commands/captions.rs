@@ -1,14 +1,22 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-/// Get the caption file path for an image (same name, .txt extension).
+use super::caption_backups::{backup_caption, CaptionBackups};
+use super::error::CommandError;
+use super::project_config::{caption_extension, caption_extension_for_image, ProjectLookup};
+use super::scan_filter::ScanIgnore;
+
+/// Get the caption file path for an image (same name, project caption extension, default .txt).
 fn caption_path_for(image_path: &str) -> PathBuf {
     let path = PathBuf::from(image_path);
-    path.with_extension("txt")
+    let ext = caption_extension_for_image(&path);
+    path.with_extension(ext)
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,7 +33,7 @@ pub struct CaptionData {
 
 /// Reads the caption file for an image. Returns tags parsed from comma-separated format.
 #[tauri::command]
-pub fn read_caption(payload: ReadCaptionPayload) -> Result<CaptionData, String> {
+pub fn read_caption(payload: ReadCaptionPayload) -> Result<CaptionData, CommandError> {
     let caption_path = caption_path_for(&payload.path);
 
     if !caption_path.exists() {
@@ -36,7 +44,7 @@ pub fn read_caption(payload: ReadCaptionPayload) -> Result<CaptionData, String>
         });
     }
 
-    let raw = fs::read_to_string(&caption_path).map_err(|e| e.to_string())?;
+    let raw = read_caption_text(&caption_path)?;
     let tags = parse_tags(&raw);
 
     Ok(CaptionData {
@@ -54,13 +62,46 @@ pub struct WriteCaptionPayload {
 
 /// Writes tags to the caption file for an image (comma-separated).
 #[tauri::command]
-pub fn write_caption(payload: WriteCaptionPayload) -> Result<(), String> {
-    let caption_path = caption_path_for(&payload.path);
+pub fn write_caption(payload: WriteCaptionPayload) -> Result<(), CommandError> {
+    let project = ProjectLookup::default();
+    let caption_path = project.caption_path(Path::new(&payload.path));
     let content = payload.tags.join(", ");
-    fs::write(&caption_path, &content).map_err(|e| e.to_string())?;
+    backup_caption(&project, Path::new(&payload.path), &caption_path);
+    fs::write(&caption_path, &content)?;
     Ok(())
 }
 
+/// Decode caption bytes: a BOM (UTF-8/UTF-16LE/BE) picks the encoding, otherwise UTF-8, then
+/// BOM-less UTF-16 (detected from NUL bytes), then Windows-1252. Returns the text and the
+/// encoding it was converted from, or None if the bytes were already BOM-less UTF-8.
+pub(super) fn decode_caption_bytes(bytes: &[u8]) -> (String, Option<&'static Encoding>) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), Some(encoding));
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        if !text.contains('\0') {
+            return (text.to_string(), None);
+        }
+    }
+    let nul_at = |parity: usize| bytes.iter().skip(parity).step_by(2).filter(|b| **b == 0).count();
+    let half = bytes.len() / 2;
+    let encoding = if bytes.len().is_multiple_of(2) && nul_at(1) > half / 2 {
+        UTF_16LE
+    } else if bytes.len().is_multiple_of(2) && nul_at(0) > half / 2 {
+        UTF_16BE
+    } else {
+        WINDOWS_1252
+    };
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), Some(encoding))
+}
+
+/// Read a caption file as text, transparently decoding UTF-16 and BOM-prefixed files.
+pub fn read_caption_text(path: impl AsRef<Path>) -> io::Result<String> {
+    fs::read(path).map(|bytes| decode_caption_bytes(&bytes).0)
+}
+
 /// Parse comma-separated tags from raw caption text.
 fn parse_tags(raw: &str) -> Vec<String> {
     raw.split(',')
@@ -77,10 +118,11 @@ pub struct AddTagPayload {
 
 /// Adds a tag to the caption file if not already present.
 #[tauri::command]
-pub fn add_tag(payload: AddTagPayload) -> Result<Vec<String>, String> {
-    let caption_path = caption_path_for(&payload.path);
+pub fn add_tag(payload: AddTagPayload) -> Result<Vec<String>, CommandError> {
+    let project = ProjectLookup::default();
+    let caption_path = project.caption_path(Path::new(&payload.path));
     let mut tags = if caption_path.exists() {
-        let raw = fs::read_to_string(&caption_path).map_err(|e| e.to_string())?;
+        let raw = read_caption_text(&caption_path)?;
         parse_tags(&raw)
     } else {
         Vec::new()
@@ -90,12 +132,86 @@ pub fn add_tag(payload: AddTagPayload) -> Result<Vec<String>, String> {
     if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
         tags.push(tag);
         let content = tags.join(", ");
-        fs::write(&caption_path, &content).map_err(|e| e.to_string())?;
+        backup_caption(&project, Path::new(&payload.path), &caption_path);
+        fs::write(&caption_path, &content)?;
     }
 
     Ok(tags)
 }
 
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionMergeMode {
+    Append,
+    Prepend,
+    #[default]
+    Replace,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WriteCaptionMergedPayload {
+    pub path: String,
+    pub new_tags: Vec<String>,
+    pub mode: CaptionMergeMode,
+}
+
+/// Push tags onto `into`, skipping empty tags and case-insensitive duplicates.
+fn push_unique_tags(into: &mut Vec<String>, tags: impl IntoIterator<Item = String>) {
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !into.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            into.push(tag);
+        }
+    }
+}
+
+/// Merge new tags into an image's caption file and write it (backing up the old caption).
+/// A trigger word, if given, is moved or added to the front. Returns the final tag list.
+pub fn merge_caption_file(
+    image_path: &str,
+    new_tags: Vec<String>,
+    mode: CaptionMergeMode,
+    trigger_word: Option<&str>,
+    project: &ProjectLookup,
+) -> Result<Vec<String>, CommandError> {
+    let caption_path = project.caption_path(Path::new(image_path));
+    let existing = if caption_path.exists() {
+        let raw = read_caption_text(&caption_path)?;
+        parse_tags(&raw)
+    } else {
+        Vec::new()
+    };
+
+    let mut tags = Vec::new();
+    if let Some(trigger) = trigger_word {
+        push_unique_tags(&mut tags, [trigger.to_string()]);
+    }
+    match mode {
+        CaptionMergeMode::Append => {
+            push_unique_tags(&mut tags, existing);
+            push_unique_tags(&mut tags, new_tags);
+        }
+        CaptionMergeMode::Prepend => {
+            push_unique_tags(&mut tags, new_tags);
+            push_unique_tags(&mut tags, existing);
+        }
+        CaptionMergeMode::Replace => push_unique_tags(&mut tags, new_tags),
+    }
+
+    let content = tags.join(", ");
+    backup_caption(project, Path::new(image_path), &caption_path);
+    fs::write(&caption_path, &content)?;
+    Ok(tags)
+}
+
+/// Merges new tags into the caption file (append, prepend, or replace), skipping
+/// case-insensitive duplicates. Returns the final tag list.
+#[tauri::command]
+pub fn write_caption_merged(payload: WriteCaptionMergedPayload) -> Result<Vec<String>, CommandError> {
+    let project = ProjectLookup::default();
+    merge_caption_file(&payload.path, payload.new_tags, payload.mode, None, &project)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RemoveTagPayload {
     pub path: String,
@@ -104,19 +220,21 @@ pub struct RemoveTagPayload {
 
 /// Removes a tag from the caption file.
 #[tauri::command]
-pub fn remove_tag(payload: RemoveTagPayload) -> Result<Vec<String>, String> {
-    let caption_path = caption_path_for(&payload.path);
+pub fn remove_tag(payload: RemoveTagPayload) -> Result<Vec<String>, CommandError> {
+    let project = ProjectLookup::default();
+    let caption_path = project.caption_path(Path::new(&payload.path));
     if !caption_path.exists() {
         return Ok(Vec::new());
     }
 
-    let raw = fs::read_to_string(&caption_path).map_err(|e| e.to_string())?;
+    let raw = read_caption_text(&caption_path)?;
     let mut tags = parse_tags(&raw);
     let tag_lower = payload.tag.trim().to_lowercase();
     tags.retain(|t| t.to_lowercase() != tag_lower);
 
     let content = tags.join(", ");
-    fs::write(&caption_path, &content).map_err(|e| e.to_string())?;
+    backup_caption(&project, Path::new(&payload.path), &caption_path);
+    fs::write(&caption_path, &content)?;
 
     Ok(tags)
 }
@@ -129,10 +247,12 @@ pub struct ReorderTagsPayload {
 
 /// Replaces all tags with the given ordered list.
 #[tauri::command]
-pub fn reorder_tags(payload: ReorderTagsPayload) -> Result<(), String> {
-    let caption_path = caption_path_for(&payload.path);
+pub fn reorder_tags(payload: ReorderTagsPayload) -> Result<(), CommandError> {
+    let project = ProjectLookup::default();
+    let caption_path = project.caption_path(Path::new(&payload.path));
     let content = payload.tags.join(", ");
-    fs::write(&caption_path, &content).map_err(|e| e.to_string())?;
+    backup_caption(&project, Path::new(&payload.path), &caption_path);
+    fs::write(&caption_path, &content)?;
     Ok(())
 }
 
@@ -158,34 +278,631 @@ pub struct ClearAllCaptionsResult {
     pub cleared_count: usize,
 }
 
-/// Clears all caption files in the project (writes empty content to each image's .txt).
+/// Clears all caption files in the project (writes empty content to each image's caption file).
 /// Uses the same walk as the project so paths match.
 #[tauri::command]
-pub fn clear_all_captions(payload: ClearAllCaptionsPayload) -> Result<ClearAllCaptionsResult, String> {
+pub fn clear_all_captions(payload: ClearAllCaptionsPayload) -> Result<ClearAllCaptionsResult, CommandError> {
     let root = PathBuf::from(&payload.root_path);
     if !root.is_dir() {
-        return Err("Project folder does not exist".to_string());
+        return Err(CommandError::not_found("Project folder does not exist"));
     }
-    let canonical = root.canonicalize().map_err(|e| e.to_string())?;
+    let canonical = root.canonicalize()?;
+    let caption_ext = caption_extension(&canonical);
+    let backups = CaptionBackups::for_root(&canonical);
     let mut cleared = 0usize;
+    let ignore = ScanIgnore::default();
     for entry in WalkDir::new(&canonical)
         .follow_links(false)
         .into_iter()
+        .filter_entry(|e| ignore.allows(e))
         .filter_map(Result::ok)
     {
         let p = entry.path();
         if !p.is_file() || !is_image_path(p) {
             continue;
         }
-        let caption_path = p.with_extension("txt");
+        let caption_path = p.with_extension(&caption_ext);
+        if let Some(backups) = &backups {
+            backups.save(p, &caption_path);
+        }
         if let Err(e) = fs::write(&caption_path, "") {
-            return Err(format!("Failed to clear {}: {}", caption_path.display(), e));
+            return Err(CommandError::from(e).context(format!("Failed to clear {}", caption_path.display())));
         }
         cleared += 1;
     }
     Ok(ClearAllCaptionsResult { cleared_count: cleared })
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReplaceInCaptionsPayload {
+    pub root_path: String,
+    pub find: String,
+    #[serde(default)]
+    pub replace: String,
+    /// Replace whole tags only (exact match) instead of substrings of the raw text.
+    #[serde(default)]
+    pub whole_tag: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Report what would change without writing any files.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplaceInCaptionsResult {
+    pub files_changed: usize,
+    pub occurrences: usize,
+    pub dry_run: bool,
+}
+
+/// Substring replace; case-insensitive matching is ASCII-only so byte offsets line up.
+fn replace_substring(raw: &str, find: &str, replace: &str, case_sensitive: bool) -> (String, usize) {
+    if case_sensitive {
+        let count = raw.matches(find).count();
+        return (raw.replace(find, replace), count);
+    }
+    let haystack = raw.to_ascii_lowercase();
+    let needle = find.to_ascii_lowercase();
+    let mut out = String::with_capacity(raw.len());
+    let mut last = 0;
+    let mut count = 0;
+    for (start, _) in haystack.match_indices(&needle) {
+        out.push_str(&raw[last..start]);
+        out.push_str(replace);
+        last = start + needle.len();
+        count += 1;
+    }
+    out.push_str(&raw[last..]);
+    (out, count)
+}
+
+/// Replace exact tag matches; an empty replacement removes the tag.
+fn replace_whole_tag(raw: &str, find: &str, replace: &str, case_sensitive: bool) -> (String, usize) {
+    let find = find.trim();
+    let replace = replace.trim();
+    let mut count = 0;
+    let tags: Vec<String> = parse_tags(raw)
+        .into_iter()
+        .filter_map(|tag| {
+            let matches = if case_sensitive {
+                tag == find
+            } else {
+                tag.eq_ignore_ascii_case(find)
+            };
+            if !matches {
+                return Some(tag);
+            }
+            count += 1;
+            (!replace.is_empty()).then(|| replace.to_string())
+        })
+        .collect();
+    (tags.join(", "), count)
+}
+
+/// Find-and-replace across every image caption in the project. Walks the tree like
+/// open_project and only touches existing caption files next to images.
+#[tauri::command]
+pub fn replace_in_captions(payload: ReplaceInCaptionsPayload) -> Result<ReplaceInCaptionsResult, CommandError> {
+    if payload.find.trim().is_empty() {
+        return Err(CommandError::invalid_input("Search text is empty"));
+    }
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err(CommandError::not_found("Project folder does not exist"));
+    }
+    let canonical = root.canonicalize()?;
+    let caption_ext = caption_extension(&canonical);
+    let backups = CaptionBackups::for_root(&canonical);
+
+    let mut files_changed = 0usize;
+    let mut occurrences = 0usize;
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
+        let p = entry.path();
+        if !p.is_file() || !is_image_path(p) {
+            continue;
+        }
+        let caption_path = p.with_extension(&caption_ext);
+        if !caption_path.is_file() {
+            continue;
+        }
+        let raw = read_caption_text(&caption_path)?;
+        let (updated, count) = if payload.whole_tag {
+            replace_whole_tag(&raw, &payload.find, &payload.replace, payload.case_sensitive)
+        } else {
+            replace_substring(&raw, &payload.find, &payload.replace, payload.case_sensitive)
+        };
+        if count == 0 {
+            continue;
+        }
+        files_changed += 1;
+        occurrences += count;
+        if !payload.dry_run {
+            if let Some(backups) = &backups {
+                backups.save(p, &caption_path);
+            }
+            fs::write(&caption_path, &updated).map_err(|e| {
+                CommandError::from(e).context(format!("Failed to write {}", caption_path.display()))
+            })?;
+        }
+    }
+
+    Ok(ReplaceInCaptionsResult {
+        files_changed,
+        occurrences,
+        dry_run: payload.dry_run,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameTagPayload {
+    pub root_path: String,
+    pub from: String,
+    /// New tag name; empty removes the tag.
+    #[serde(default)]
+    pub to: String,
+}
+
+/// Rename `from` to `to` in a tag list (case-insensitive match). If `to` is already present
+/// the duplicates collapse into the first occurrence. Returns None when nothing changed.
+fn rename_tag_in(tags: &[String], from: &str, to: &str) -> Option<Vec<String>> {
+    if !tags.iter().any(|t| t.eq_ignore_ascii_case(from)) {
+        return None;
+    }
+    let mut renamed: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = if tag.eq_ignore_ascii_case(from) { to } else { tag.as_str() };
+        if !tag.is_empty() && !renamed.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            renamed.push(tag.to_string());
+        }
+    }
+    Some(renamed)
+}
+
+/// Renames (or, with an empty `to`, removes) a tag in every caption in the project.
+/// Returns the relative paths of the images whose captions changed.
+#[tauri::command]
+pub fn rename_tag(payload: RenameTagPayload) -> Result<Vec<String>, CommandError> {
+    let from = payload.from.trim();
+    let to = payload.to.trim();
+    if from.is_empty() {
+        return Err(CommandError::invalid_input("Tag to rename is empty"));
+    }
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err(CommandError::not_found("Project folder does not exist"));
+    }
+    let canonical = root.canonicalize()?;
+    let caption_ext = caption_extension(&canonical);
+    let backups = CaptionBackups::for_root(&canonical);
+
+    let mut affected = Vec::new();
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
+        let p = entry.path();
+        if !p.is_file() || !is_image_path(p) {
+            continue;
+        }
+        let caption_path = p.with_extension(&caption_ext);
+        if !caption_path.is_file() {
+            continue;
+        }
+        let raw = read_caption_text(&caption_path)?;
+        let Some(tags) = rename_tag_in(&parse_tags(&raw), from, to) else {
+            continue;
+        };
+        if let Some(backups) = &backups {
+            backups.save(p, &caption_path);
+        }
+        fs::write(&caption_path, tags.join(", ")).map_err(|e| {
+            CommandError::from(e).context(format!("Failed to write {}", caption_path.display()))
+        })?;
+        let relative = p
+            .strip_prefix(&canonical)
+            .unwrap_or(p)
+            .to_string_lossy()
+            .replace('\\', "/");
+        affected.push(relative);
+    }
+
+    Ok(affected)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyTagAliasesPayload {
+    pub root_path: String,
+    /// CSV of `from,to` rows, or Danbooru tag_aliases JSON
+    /// (`[{ "antecedent_name": ..., "consequent_name": ... }]`) or a plain `{ "from": "to" }` object.
+    pub aliases_path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApplyTagAliasesResult {
+    pub alias_count: usize,
+    pub files_changed: usize,
+    pub tags_changed: usize,
+}
+
+/// Alias lookup key: case-insensitive, with Danbooru underscores treated as spaces.
+fn alias_key(tag: &str) -> String {
+    tag.trim().to_lowercase().replace('_', " ")
+}
+
+/// Load an alias table into normalized-from -> to.
+fn load_tag_aliases(path: &Path) -> Result<HashMap<String, String>, CommandError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| CommandError::from(e).context("Failed to read aliases"))?;
+    let mut pairs: Vec<(String, String)> = Vec::new();
+
+    let is_json = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    if is_json {
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| CommandError::from(e).context("Invalid aliases JSON"))?;
+        match value {
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    let from = item.get("antecedent_name").and_then(|v| v.as_str());
+                    let to = item.get("consequent_name").and_then(|v| v.as_str());
+                    // Danbooru also lists deleted/retired aliases; only apply active ones.
+                    let active = item
+                        .get("status")
+                        .and_then(|v| v.as_str())
+                        .is_none_or(|s| s == "active");
+                    if let (Some(from), Some(to), true) = (from, to, active) {
+                        pairs.push((from.to_string(), to.to_string()));
+                    }
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for (from, to) in map {
+                    if let Some(to) = to.as_str() {
+                        pairs.push((from, to.to_string()));
+                    }
+                }
+            }
+            _ => {
+                return Err(CommandError::invalid_input(
+                    "Aliases JSON must be an array or an object",
+                ))
+            }
+        }
+    } else {
+        for row in parse_csv(&content) {
+            if let [from, to, ..] = row.as_slice() {
+                let from = from.trim();
+                let header = from.eq_ignore_ascii_case("from") || from.eq_ignore_ascii_case("antecedent_name");
+                if !header {
+                    pairs.push((from.to_string(), to.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(pairs
+        .into_iter()
+        .filter(|(from, to)| !from.trim().is_empty() && !to.trim().is_empty())
+        .map(|(from, to)| (alias_key(&from), to.trim().to_string()))
+        .filter(|(from, to)| *from != alias_key(to))
+        .collect())
+}
+
+/// Apply aliases to a tag list, collapsing duplicates into the first occurrence. The replacement
+/// follows the original tag's style (underscores only if the original used them).
+/// Returns None when no tag was aliased.
+fn apply_aliases_in(tags: &[String], aliases: &HashMap<String, String>) -> Option<(Vec<String>, usize)> {
+    let mut changed = 0usize;
+    let mut out: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = match aliases.get(&alias_key(tag)) {
+            Some(to) => {
+                changed += 1;
+                if tag.contains('_') {
+                    to.clone()
+                } else {
+                    to.replace('_', " ")
+                }
+            }
+            None => tag.clone(),
+        };
+        if !out.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            out.push(tag);
+        }
+    }
+    (changed > 0).then_some((out, changed))
+}
+
+/// Rewrite every caption in the project through a tag alias table (e.g. Danbooru's),
+/// walking the tree once.
+#[tauri::command]
+pub fn apply_tag_aliases(payload: ApplyTagAliasesPayload) -> Result<ApplyTagAliasesResult, CommandError> {
+    let aliases = load_tag_aliases(Path::new(&payload.aliases_path))?;
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err(CommandError::not_found("Project folder does not exist"));
+    }
+    let canonical = root.canonicalize()?;
+    let caption_ext = caption_extension(&canonical);
+    let backups = CaptionBackups::for_root(&canonical);
+
+    let mut files_changed = 0usize;
+    let mut tags_changed = 0usize;
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
+        let p = entry.path();
+        if !p.is_file() || !is_image_path(p) {
+            continue;
+        }
+        let caption_path = p.with_extension(&caption_ext);
+        if !caption_path.is_file() {
+            continue;
+        }
+        let raw = read_caption_text(&caption_path)?;
+        let Some((tags, changed)) = apply_aliases_in(&parse_tags(&raw), &aliases) else {
+            continue;
+        };
+        if let Some(backups) = &backups {
+            backups.save(p, &caption_path);
+        }
+        fs::write(&caption_path, tags.join(", ")).map_err(|e| {
+            CommandError::from(e).context(format!("Failed to write {}", caption_path.display()))
+        })?;
+        files_changed += 1;
+        tags_changed += changed;
+    }
+
+    Ok(ApplyTagAliasesResult {
+        alias_count: aliases.len(),
+        files_changed,
+        tags_changed,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagFrequencyPayload {
+    pub root_path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagFrequency {
+    pub tag: String,
+    /// Total occurrences across all captions.
+    pub count: usize,
+    /// Number of images whose caption contains the tag.
+    pub image_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagFrequencyResult {
+    pub tags: Vec<TagFrequency>,
+    pub captioned_image_count: usize,
+}
+
+#[derive(Default)]
+struct TagTally {
+    count: usize,
+    image_count: usize,
+    /// Surface spellings in first-seen order with their counts.
+    forms: Vec<(String, usize)>,
+}
+
+/// Counts tags across all image captions in the project (case-insensitive), sorted by count
+/// descending. Each tag is reported in its most common spelling.
+#[tauri::command]
+pub fn tag_frequency(payload: TagFrequencyPayload) -> Result<TagFrequencyResult, CommandError> {
+    Ok(count_project_tags(&payload.root_path)?)
+}
+
+/// Shared by tag_frequency and tag_vocabulary: one walk over the project's captions.
+fn count_project_tags(root_path: &str) -> Result<TagFrequencyResult, String> {
+    let root = PathBuf::from(root_path);
+    if !root.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+    let canonical = root.canonicalize().map_err(|e| e.to_string())?;
+    let caption_ext = caption_extension(&canonical);
+
+    let mut tallies: HashMap<String, TagTally> = HashMap::new();
+    let mut captioned_image_count = 0usize;
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
+        let p = entry.path();
+        if !p.is_file() || !is_image_path(p) {
+            continue;
+        }
+        let Ok(raw) = read_caption_text(p.with_extension(&caption_ext)) else {
+            continue;
+        };
+        let tags = parse_tags(&raw);
+        if tags.is_empty() {
+            continue;
+        }
+        captioned_image_count += 1;
+
+        let mut seen_in_image: Vec<String> = Vec::new();
+        for tag in tags {
+            let key = tag.to_lowercase();
+            let tally = tallies.entry(key.clone()).or_default();
+            tally.count += 1;
+            match tally.forms.iter_mut().find(|(form, _)| *form == tag) {
+                Some((_, n)) => *n += 1,
+                None => tally.forms.push((tag, 1)),
+            }
+            if !seen_in_image.contains(&key) {
+                tally.image_count += 1;
+                seen_in_image.push(key);
+            }
+        }
+    }
+
+    let mut tags: Vec<TagFrequency> = tallies
+        .into_values()
+        .map(|tally| {
+            // max_by_key keeps the last maximum, so reverse to prefer the first-seen spelling on ties.
+            let tag = tally
+                .forms
+                .into_iter()
+                .rev()
+                .max_by_key(|(_, n)| *n)
+                .map(|(form, _)| form)
+                .unwrap_or_default();
+            TagFrequency {
+                tag,
+                count: tally.count,
+                image_count: tally.image_count,
+            }
+        })
+        .collect();
+    tags.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.tag.to_lowercase().cmp(&b.tag.to_lowercase()))
+    });
+
+    Ok(TagFrequencyResult {
+        tags,
+        captioned_image_count,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagVocabularyPayload {
+    pub root_path: String,
+    /// Only return tags starting with this text (case-insensitive), for incremental autocomplete.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Max number of entries to return (most frequent first).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagVocabularyEntry {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Distinct tags used in the project with their frequencies, most frequent first.
+/// Feeds tag autocomplete.
+#[tauri::command]
+pub fn tag_vocabulary(payload: TagVocabularyPayload) -> Result<Vec<TagVocabularyEntry>, CommandError> {
+    let prefix = payload
+        .prefix
+        .as_deref()
+        .map(|p| p.trim().to_lowercase())
+        .unwrap_or_default();
+    let entries = count_project_tags(&payload.root_path)?
+        .tags
+        .into_iter()
+        .filter(|t| t.tag.to_lowercase().starts_with(&prefix))
+        .take(payload.limit.unwrap_or(usize::MAX))
+        .map(|t| TagVocabularyEntry {
+            tag: t.tag,
+            count: t.count,
+        })
+        .collect();
+    Ok(entries)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CaptionStatsPayload {
+    pub root_path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageCaptionStats {
+    pub relative_path: String,
+    pub char_count: usize,
+    pub tag_count: usize,
+    pub word_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CaptionStatsResult {
+    /// Sorted by relative path.
+    pub images: Vec<ImageCaptionStats>,
+    pub min_tag_count: usize,
+    pub max_tag_count: usize,
+    pub mean_tag_count: f64,
+    /// Images with a missing or blank caption.
+    pub empty_caption_count: usize,
+}
+
+/// Caption length stats per image plus project aggregates, for spotting over-long and empty captions.
+#[tauri::command]
+pub fn caption_stats(payload: CaptionStatsPayload) -> Result<CaptionStatsResult, CommandError> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err(CommandError::not_found("Project folder does not exist"));
+    }
+    let canonical = root.canonicalize()?;
+    let caption_ext = caption_extension(&canonical);
+
+    let mut images = Vec::new();
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
+        let p = entry.path();
+        if !p.is_file() || !is_image_path(p) {
+            continue;
+        }
+        let raw = read_caption_text(p.with_extension(&caption_ext)).unwrap_or_default();
+        let raw = raw.trim();
+        let relative_path = p
+            .strip_prefix(&canonical)
+            .unwrap_or(p)
+            .to_string_lossy()
+            .replace('\\', "/");
+        images.push(ImageCaptionStats {
+            relative_path,
+            char_count: raw.chars().count(),
+            tag_count: parse_tags(raw).len(),
+            word_count: raw.split_whitespace().count(),
+        });
+    }
+    images.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let tag_counts = images.iter().map(|i| i.tag_count);
+    let mean_tag_count = if images.is_empty() {
+        0.0
+    } else {
+        tag_counts.clone().sum::<usize>() as f64 / images.len() as f64
+    };
+    Ok(CaptionStatsResult {
+        min_tag_count: tag_counts.clone().min().unwrap_or(0),
+        max_tag_count: tag_counts.max().unwrap_or(0),
+        mean_tag_count,
+        empty_caption_count: images.iter().filter(|i| i.char_count == 0).count(),
+        images,
+    })
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetCaptionsBatchPayload {
     pub paths: Vec<String>,
@@ -198,15 +915,16 @@ pub struct CaptionsBatchResult {
 
 /// Read captions for multiple images in parallel
 #[tauri::command]
-pub fn get_captions_batch(payload: GetCaptionsBatchPayload) -> Result<CaptionsBatchResult, String> {
+pub fn get_captions_batch(payload: GetCaptionsBatchPayload) -> Result<CaptionsBatchResult, CommandError> {
+    let project = ProjectLookup::default();
     let captions: HashMap<String, CaptionData> = payload
         .paths
         .par_iter()
         .filter_map(|path_str| {
-            let caption_path = caption_path_for(path_str);
+            let caption_path = project.caption_path(Path::new(path_str));
             
             let caption_data = if caption_path.exists() {
-                match fs::read_to_string(&caption_path) {
+                match read_caption_text(&caption_path) {
                     Ok(raw) => {
                         let tags = parse_tags(&raw);
                         CaptionData {
@@ -235,3 +953,373 @@ pub fn get_captions_batch(payload: GetCaptionsBatchPayload) -> Result<CaptionsBa
 
     Ok(CaptionsBatchResult { captions })
 }
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionImportFormat {
+    /// Kohya-style metadata.json: { "<image key>": { "caption": "..." } } (or "<key>": "...").
+    Metadata,
+    /// CSV with a caption (or text) column and a relative_path, filename or file_name column.
+    Csv,
+    /// metadata.jsonl: one { "file_name": "...", "text": "..." } object per line.
+    Jsonl,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportCaptionsPayload {
+    pub root_path: String,
+    pub source_path: String,
+    pub format: CaptionImportFormat,
+    /// Keep captions that already exist instead of replacing them.
+    #[serde(default)]
+    pub skip_existing: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportCaptionsResult {
+    pub matched_count: usize,
+    pub written_count: usize,
+    pub skipped_existing_count: usize,
+    /// Keys from the source file that matched no project image.
+    pub unmatched: Vec<String>,
+}
+
+/// Split CSV text into rows of fields (RFC 4180: quoted fields may contain commas, quotes and
+/// line breaks).
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// (image key, caption) pairs from an import source file.
+fn read_caption_import(
+    path: &Path,
+    format: CaptionImportFormat,
+) -> Result<Vec<(String, String)>, CommandError> {
+    let text = read_caption_text(path)?;
+    let text = text.as_str();
+    let mut entries = Vec::new();
+    match format {
+        CaptionImportFormat::Metadata => {
+            let map: serde_json::Map<String, serde_json::Value> = serde_json::from_str(text)?;
+            for (key, value) in map {
+                let caption = match &value {
+                    serde_json::Value::String(s) => Some(s.as_str()),
+                    _ => value
+                        .get("caption")
+                        .or_else(|| value.get("tags"))
+                        .and_then(|c| c.as_str()),
+                };
+                if let Some(caption) = caption {
+                    entries.push((key, caption.to_string()));
+                }
+            }
+        }
+        CaptionImportFormat::Jsonl => {
+            for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+                let value: serde_json::Value = serde_json::from_str(line)
+                    .map_err(|e| CommandError::from(e).context(format!("Line {}", i + 1)))?;
+                let key = value.get("file_name").and_then(|v| v.as_str());
+                let caption = value.get("text").and_then(|v| v.as_str());
+                if let (Some(key), Some(caption)) = (key, caption) {
+                    entries.push((key.to_string(), caption.to_string()));
+                }
+            }
+        }
+        CaptionImportFormat::Csv => {
+            let mut rows = parse_csv(text).into_iter();
+            let header: Vec<String> = rows
+                .next()
+                .unwrap_or_default()
+                .iter()
+                .map(|h| h.trim().to_lowercase())
+                .collect();
+            let column =
+                |names: &[&str]| names.iter().find_map(|n| header.iter().position(|h| h == n));
+            let key_col = column(&["relative_path", "file_name", "filename", "file", "image"])
+                .ok_or_else(|| {
+                    CommandError::invalid_input(
+                        "CSV has no relative_path, filename or file_name column",
+                    )
+                })?;
+            let caption_col = column(&["caption", "text", "tags"])
+                .ok_or_else(|| CommandError::invalid_input("CSV has no caption or text column"))?;
+            for row in rows {
+                if let (Some(key), Some(caption)) = (row.get(key_col), row.get(caption_col)) {
+                    if !key.trim().is_empty() {
+                        entries.push((key.trim().to_string(), caption.clone()));
+                    }
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Lookup tables from import keys to project images: relative path and file name, each with and
+/// without extension, exact and lowercased.
+struct ImageIndex {
+    exact: HashMap<String, PathBuf>,
+    folded: HashMap<String, PathBuf>,
+}
+
+impl ImageIndex {
+    fn build(root: &Path) -> Self {
+        let mut exact = HashMap::new();
+        let mut folded = HashMap::new();
+        let ignore = ScanIgnore::default();
+        for entry in WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| ignore.allows(e))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && is_image_path(e.path()))
+        {
+            let path = entry.path();
+            let rel = path
+                .strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let rel_stem = rel
+                .rsplit_once('.')
+                .map(|(s, _)| s.to_string())
+                .unwrap_or_else(|| rel.clone());
+            let name = rel.rsplit('/').next().unwrap_or(&rel).to_string();
+            let name_stem = rel_stem.rsplit('/').next().unwrap_or(&rel_stem).to_string();
+            // Relative paths are unique and win over bare file names from other folders.
+            for (key, unique) in [(rel, true), (rel_stem, true), (name, false), (name_stem, false)] {
+                let insert = |map: &mut HashMap<String, PathBuf>, key: String| {
+                    if unique {
+                        map.insert(key, path.to_path_buf());
+                    } else {
+                        map.entry(key).or_insert_with(|| path.to_path_buf());
+                    }
+                };
+                insert(&mut folded, key.to_lowercase());
+                insert(&mut exact, key);
+            }
+        }
+        Self { exact, folded }
+    }
+
+    fn find(&self, key: &str) -> Option<&PathBuf> {
+        let key = key.trim().trim_start_matches("./").replace('\\', "/");
+        self.exact
+            .get(&key)
+            .or_else(|| self.folded.get(&key.to_lowercase()))
+    }
+}
+
+/// Split a caption mapping (metadata.json, CSV or metadata.jsonl) into per-image caption files.
+/// Keys are matched to images by relative path or file name (with or without extension), falling
+/// back to a case-insensitive match. Replaced captions are backed up first.
+#[tauri::command]
+pub fn import_captions(payload: ImportCaptionsPayload) -> Result<ImportCaptionsResult, CommandError> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err(CommandError::not_found("Project folder does not exist"));
+    }
+    let canonical = root.canonicalize()?;
+    let entries = read_caption_import(Path::new(&payload.source_path), payload.format)?;
+    let index = ImageIndex::build(&canonical);
+    let caption_ext = caption_extension(&canonical);
+    let backups = CaptionBackups::for_root(&canonical);
+
+    let mut result = ImportCaptionsResult {
+        matched_count: 0,
+        written_count: 0,
+        skipped_existing_count: 0,
+        unmatched: Vec::new(),
+    };
+    for (key, caption) in entries {
+        let Some(image) = index.find(&key) else {
+            result.unmatched.push(key);
+            continue;
+        };
+        result.matched_count += 1;
+        let caption_path = image.with_extension(&caption_ext);
+        if payload.skip_existing && caption_path.exists() {
+            result.skipped_existing_count += 1;
+            continue;
+        }
+        if let Some(backups) = &backups {
+            backups.save(image, &caption_path);
+        }
+        fs::write(&caption_path, caption.trim()).map_err(|e| {
+            CommandError::from(e).context(format!("Failed to write {}", caption_path.display()))
+        })?;
+        result.written_count += 1;
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NormalizeCaptionEncodingPayload {
+    pub root_path: String,
+    /// Only report the files that would be rewritten.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertedCaption {
+    pub relative_path: String,
+    /// Encoding the file was in, e.g. "UTF-16LE" or "UTF-8" (with BOM).
+    pub encoding: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NormalizeCaptionEncodingResult {
+    pub scanned_count: usize,
+    pub converted: Vec<ConvertedCaption>,
+}
+
+/// Rewrite caption files that are UTF-16, BOM-prefixed or not valid UTF-8 as plain UTF-8 with
+/// the same text. If the project has caption backups enabled, each file is backed up in its
+/// original encoding before it is rewritten.
+#[tauri::command]
+pub fn normalize_caption_encoding(
+    payload: NormalizeCaptionEncodingPayload,
+) -> Result<NormalizeCaptionEncodingResult, CommandError> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err(CommandError::not_found("Project folder does not exist"));
+    }
+    let canonical = root.canonicalize()?;
+    let caption_ext = caption_extension(&canonical);
+    let backups = CaptionBackups::for_root(&canonical);
+
+    let mut scanned_count = 0usize;
+    let mut converted = Vec::new();
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
+        let p = entry.path();
+        if !p.is_file() || !is_image_path(p) {
+            continue;
+        }
+        let caption_path = p.with_extension(&caption_ext);
+        let Ok(bytes) = fs::read(&caption_path) else {
+            continue;
+        };
+        scanned_count += 1;
+        let (text, Some(encoding)) = decode_caption_bytes(&bytes) else {
+            continue;
+        };
+        if !payload.dry_run {
+            if let Some(backups) = &backups {
+                backups.save(p, &caption_path);
+            }
+            fs::write(&caption_path, text).map_err(|e| {
+                CommandError::from(e).context(format!("Failed to write {}", caption_path.display()))
+            })?;
+        }
+        converted.push(ConvertedCaption {
+            relative_path: caption_path
+                .strip_prefix(&canonical)
+                .unwrap_or(&caption_path)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            encoding: encoding.name().to_string(),
+        });
+    }
+    Ok(NormalizeCaptionEncodingResult {
+        scanned_count,
+        converted,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProposedCaption {
+    /// Image path.
+    pub path: String,
+    pub new_caption: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewCaptionChangesPayload {
+    pub changes: Vec<ProposedCaption>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CaptionChangePreview {
+    pub path: String,
+    /// Whether the image has a caption file now.
+    pub exists: bool,
+    pub existing: String,
+    pub proposed: String,
+    /// Tags in the proposed caption but not the existing one (case-insensitive).
+    pub added: Vec<String>,
+    /// Tags in the existing caption that the proposed one drops.
+    pub removed: Vec<String>,
+    /// Same tags in the same order; writing would change nothing.
+    pub unchanged: bool,
+}
+
+/// Tags of `a` that have no case-insensitive match in `b`.
+fn tags_missing_from(a: &[String], b: &[String]) -> Vec<String> {
+    a.iter()
+        .filter(|t| !b.iter().any(|o| o.eq_ignore_ascii_case(t)))
+        .cloned()
+        .collect()
+}
+
+/// Compare proposed captions with the current caption files without writing anything, so the
+/// frontend can review a batch before committing it. Results are in input order.
+#[tauri::command]
+pub fn preview_caption_changes(
+    payload: PreviewCaptionChangesPayload,
+) -> Result<Vec<CaptionChangePreview>, CommandError> {
+    let project = ProjectLookup::default();
+    Ok(payload
+        .changes
+        .into_par_iter()
+        .map(|change| {
+            let caption_path = project.caption_path(Path::new(&change.path));
+            let current = read_caption_text(&caption_path).ok();
+            let existing = current.as_deref().unwrap_or("").trim().to_string();
+            let existing_tags = parse_tags(&existing);
+            let proposed_tags = parse_tags(&change.new_caption);
+            let unchanged = existing_tags == proposed_tags;
+            CaptionChangePreview {
+                added: tags_missing_from(&proposed_tags, &existing_tags),
+                removed: tags_missing_from(&existing_tags, &proposed_tags),
+                unchanged,
+                exists: current.is_some(),
+                existing,
+                proposed: change.new_caption.trim().to_string(),
+                path: change.path,
+            }
+        })
+        .collect())
+}
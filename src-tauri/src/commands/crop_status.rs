@@ -1,10 +1,62 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
+use std::io;
 use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use super::metadata_store;
+use super::scan_filter::ScanIgnore;
 
 const CROP_STATUS_FILE: &str = ".lora-studio/crop_status.json";
 
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CropStatus {
+    #[default]
+    Uncropped,
+    Cropped,
+    /// Source image that multi_crop produced several crops from.
+    Multi,
+    /// Source image that has a saved-as-new crop.
+    HasCrop,
+    Flagged,
+    NeedsRecrop,
+    Skipped,
+}
+
+impl CropStatus {
+    pub const ALL: [CropStatus; 7] = [
+        CropStatus::Uncropped,
+        CropStatus::Cropped,
+        CropStatus::Multi,
+        CropStatus::HasCrop,
+        CropStatus::Flagged,
+        CropStatus::NeedsRecrop,
+        CropStatus::Skipped,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CropStatus::Uncropped => "uncropped",
+            CropStatus::Cropped => "cropped",
+            CropStatus::Multi => "multi",
+            CropStatus::HasCrop => "has_crop",
+            CropStatus::Flagged => "flagged",
+            CropStatus::NeedsRecrop => "needs_recrop",
+            CropStatus::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| format!("Unknown crop status: {:?}", s))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CropStatusData {
     pub statuses: HashMap<String, String>,
@@ -14,30 +66,23 @@ fn crop_status_path(root_path: &str) -> PathBuf {
     PathBuf::from(root_path).join(CROP_STATUS_FILE)
 }
 
-fn ensure_lora_studio_dir(root_path: &str) -> Result<(), String> {
-    let dir = PathBuf::from(root_path).join(".lora-studio");
-    if !dir.exists() {
-        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    }
-    Ok(())
-}
-
-fn load_crop_statuses(root_path: &str) -> Result<CropStatusData, String> {
-    let path = crop_status_path(root_path);
-    if !path.exists() {
-        return Ok(CropStatusData {
-            statuses: HashMap::new(),
-        });
-    }
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+pub fn load_crop_statuses(root_path: &str) -> Result<CropStatusData, String> {
+    let content = match metadata_store::read_to_string(&crop_status_path(root_path)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(CropStatusData {
+                statuses: HashMap::new(),
+            });
+        }
+        Err(e) => return Err(e.to_string()),
+    };
     serde_json::from_str(&content).map_err(|e| e.to_string())
 }
 
-fn save_crop_statuses(root_path: &str, data: &CropStatusData) -> Result<(), String> {
-    ensure_lora_studio_dir(root_path)?;
-    let path = crop_status_path(root_path);
+/// Save crop statuses; like ratings, the write is atomic and debounced.
+pub fn save_crop_statuses(root_path: &str, data: &CropStatusData) -> Result<(), String> {
     let content = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
-    fs::write(&path, content).map_err(|e| e.to_string())
+    metadata_store::write_debounced(&crop_status_path(root_path), content)
 }
 
 #[derive(Debug, Deserialize)]
@@ -47,14 +92,16 @@ pub struct SetCropStatusPayload {
     pub status: String,
 }
 
+/// Set an image's crop status. Unknown statuses are rejected; "uncropped" removes the entry.
 #[tauri::command]
 pub fn set_crop_status(payload: SetCropStatusPayload) -> Result<(), String> {
+    let status = CropStatus::parse(payload.status.trim())?;
     let mut data = load_crop_statuses(&payload.root_path)?;
-    if payload.status == "uncropped" {
+    if status == CropStatus::Uncropped {
         data.statuses.remove(&payload.relative_path);
     } else {
         data.statuses
-            .insert(payload.relative_path, payload.status);
+            .insert(payload.relative_path, status.as_str().to_string());
     }
     save_crop_statuses(&payload.root_path, &data)
 }
@@ -82,3 +129,47 @@ pub fn clear_all_crop_statuses(payload: GetCropStatusesPayload) -> Result<usize,
     save_crop_statuses(&payload.root_path, &empty)?;
     Ok(count)
 }
+
+/// Number of project images in each crop status. Images without an entry count as "uncropped";
+/// entries for files that no longer exist are ignored.
+#[tauri::command]
+pub fn crop_status_counts(payload: GetCropStatusesPayload) -> Result<HashMap<String, usize>, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let data = load_crop_statuses(&payload.root_path)?;
+    let mut counts: HashMap<String, usize> = CropStatus::ALL
+        .iter()
+        .map(|status| (status.as_str().to_string(), 0))
+        .collect();
+
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        let is_image = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+        if !path.is_file() || !is_image {
+            continue;
+        }
+        let relative_path = path
+            .strip_prefix(&root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let status = data
+            .statuses
+            .get(&relative_path)
+            .and_then(|s| CropStatus::parse(s).ok())
+            .unwrap_or_default();
+        *counts.entry(status.as_str().to_string()).or_default() += 1;
+    }
+    Ok(counts)
+}
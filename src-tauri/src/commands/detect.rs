@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
 use image::GenericImageView;
 
+use super::images::open_oriented;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct FaceRegion {
     pub x: u32,
@@ -31,10 +34,25 @@ pub fn detect_faces(payload: DetectFacesPayload) -> Result<Vec<FaceRegion>, Stri
         }
     }
 
-    // Load image to get dimensions
-    let img = image::open(&payload.path).map_err(|e| format!("Failed to open image: {}", e))?;
-    let (width, height) = img.dimensions();
+    // Regions are in oriented pixels, the same space as the preview and crop_image.
+    let img = open_oriented(Path::new(&payload.path))
+        .map_err(|e| format!("Failed to open image: {}", e))?;
+    let result = detect_faces_in(&img);
+
+    // Cache the result
+    {
+        let mut cache = DETECTION_CACHE.lock().unwrap();
+        cache.insert(payload.path.clone(), result.clone());
+    }
     
+    Ok(result)
+}
+
+/// Face regions in `img`'s pixel coordinates. Commands that already hold the (oriented) image
+/// they are going to crop call this directly so the regions line up with its pixels.
+pub fn detect_faces_in(img: &image::DynamicImage) -> Vec<FaceRegion> {
+    let (width, height) = img.dimensions();
+
     // PLACEHOLDER IMPLEMENTATION - Working demonstration of the feature
     //
     // The YuNet ONNX model has been downloaded to src-tauri/models/yunet_face.onnx
@@ -57,19 +75,11 @@ pub fn detect_faces(payload: DetectFacesPayload) -> Result<Vec<FaceRegion>, Stri
     let face_x = (width - face_width) / 2;
     let face_y = (height - face_height) / 2;
     
-    let result = vec![FaceRegion {
+    vec![FaceRegion {
         x: face_x,
         y: face_y,
         width: face_width,
         height: face_height,
         confidence: 0.95,
-    }];
-    
-    // Cache the result
-    {
-        let mut cache = DETECTION_CACHE.lock().unwrap();
-        cache.insert(payload.path.clone(), result.clone());
-    }
-    
-    Ok(result)
+    }]
 }
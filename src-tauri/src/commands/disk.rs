@@ -0,0 +1,50 @@
+//! Free and total space of the filesystem holding a path, so exports can check for room first.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskStats {
+    pub mount_point: String,
+    pub free_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Strip the `\\?\` prefix canonicalize adds on Windows so paths compare with mount points.
+fn plain_path(path: PathBuf) -> PathBuf {
+    match path.to_str().and_then(|s| s.strip_prefix(r"\\?\")) {
+        Some(stripped) => PathBuf::from(stripped),
+        None => path,
+    }
+}
+
+/// Stats for the disk whose mount point is the longest prefix of `path`. The path does not need
+/// to exist yet (e.g. an export destination); its nearest existing ancestor is used.
+pub fn disk_stats_for(path: &Path) -> Option<DiskStats> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let canonical = plain_path(existing.canonicalize().ok()?);
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| canonical.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| DiskStats {
+            mount_point: disk.mount_point().to_string_lossy().into_owned(),
+            free_bytes: disk.available_space(),
+            total_bytes: disk.total_space(),
+        })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiskStatsPayload {
+    pub path: String,
+}
+
+/// Free and total bytes of the filesystem containing `path`.
+#[tauri::command]
+pub fn disk_stats(payload: DiskStatsPayload) -> Result<DiskStats, String> {
+    disk_stats_for(Path::new(&payload.path))
+        .ok_or_else(|| format!("Could not determine the disk for {}", payload.path))
+}
@@ -0,0 +1,122 @@
+//! Structured command errors. Serialized as `{ "code": "not_found", "message": "..." }` so the
+//! frontend can branch on `code` and still show `message` (it already reads `err.message`).
+
+use serde::Serialize;
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    PermissionDenied,
+    AlreadyExists,
+    InvalidInput,
+    /// An image or metadata file could not be decoded/parsed.
+    Decode,
+    /// An image could not be encoded in the requested format.
+    Encode,
+    /// The file format is not supported.
+    Unsupported,
+    /// Moving a file to the OS trash failed; nothing was deleted.
+    TrashFailed,
+    /// Other filesystem error (disk full, interrupted, ...).
+    Io,
+    /// Not classified; see the message.
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    /// Prefix the message with context, keeping the code.
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        Self::new(self.code, format!("{}: {}", context, self.message))
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Errors from helpers that still return `Result<_, String>`.
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Other, message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        Self::new(ErrorCode::Other, message)
+    }
+}
+
+/// Lets `String`-returning helpers call helpers that return CommandError with `?`.
+impl From<CommandError> for String {
+    fn from(err: CommandError) -> Self {
+        err.message
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(err: io::Error) -> Self {
+        let code = match err.kind() {
+            io::ErrorKind::NotFound => ErrorCode::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            io::ErrorKind::AlreadyExists => ErrorCode::AlreadyExists,
+            io::ErrorKind::InvalidInput => ErrorCode::InvalidInput,
+            io::ErrorKind::InvalidData => ErrorCode::Decode,
+            _ => ErrorCode::Io,
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+impl From<image::ImageError> for CommandError {
+    fn from(err: image::ImageError) -> Self {
+        use image::ImageError;
+        let code = match &err {
+            ImageError::IoError(e) => return CommandError::from(io::Error::new(e.kind(), err.to_string())),
+            ImageError::Decoding(_) | ImageError::Limits(_) => ErrorCode::Decode,
+            ImageError::Encoding(_) => ErrorCode::Encode,
+            ImageError::Unsupported(_) => ErrorCode::Unsupported,
+            ImageError::Parameter(_) => ErrorCode::InvalidInput,
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(err: serde_json::Error) -> Self {
+        let code = if err.is_io() {
+            ErrorCode::Io
+        } else {
+            ErrorCode::Decode
+        };
+        Self::new(code, err.to_string())
+    }
+}
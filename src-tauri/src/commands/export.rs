@@ -1,12 +1,22 @@
-//! Export dataset: copy images + .txt captions to a folder or ZIP.
-//! Supports filtering by relative paths and "only captioned"; optional trigger word and sequential naming.
+//! Export dataset: copy images + caption sidecars (.txt by default) to a folder, ZIP, or WebDataset .tar shards.
+//! Supports filtering by relative paths, "only captioned" and required/excluded tags; optional trigger word and sequential naming.
+//! Folder and ZIP exports can also use the Kohya `N_concept/` layout and/or a single metadata.json.
 
+use image::ImageReader;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use super::captions::read_caption_text;
+use super::disk::disk_stats_for;
+use super::error::CommandError;
+use super::project_config::caption_extension;
 use super::ratings::{load_ratings, ImageRating, RatingsData};
+use super::scan_filter::ScanIgnore;
 
 const IMAGE_EXT: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
 
@@ -18,12 +28,66 @@ fn is_image(p: &Path) -> bool {
     IMAGE_EXT.iter().any(|&e| e.eq_ignore_ascii_case(&ext))
 }
 
-fn caption_path(img: &Path) -> PathBuf {
-    img.with_extension("txt")
+fn caption_path(img: &Path, caption_ext: &str) -> PathBuf {
+    img.with_extension(caption_ext)
 }
 
 // ============ Export to folder or ZIP ============
 
+/// How captions are written alongside exported images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionFormat {
+    /// One caption file per image (same base name, project caption extension).
+    #[default]
+    Txt,
+    /// Single Kohya-style metadata.json at the export root: { "<image key>": { "caption": "..." } }.
+    Metadata,
+    /// Single captions.csv at the export root for spreadsheet review:
+    /// filename,relative_path,caption,rating,width,height.
+    Csv,
+    /// Hugging Face `imagefolder` metadata.jsonl at the export root: one
+    /// {"file_name": "...", "text": "..."} object per image.
+    Jsonl,
+}
+
+/// Kohya DreamBooth layout: images are placed in `<repeat_count>_<concept_name>/`.
+#[derive(Debug, Deserialize)]
+pub struct KohyaFolderOptions {
+    pub repeat_count: u32,
+    pub concept_name: String,
+}
+
+/// One Kohya concept of a multi-concept export: a subset of images routed into its own `N_name/` folder.
+#[derive(Debug, Deserialize)]
+pub struct KohyaConcept {
+    pub relative_paths: Vec<String>,
+    #[serde(flatten)]
+    pub folder: KohyaFolderOptions,
+}
+
+impl KohyaFolderOptions {
+    /// `<repeats>_<concept>`. The concept becomes a single path component under the export
+    /// destination (and in ZIP entry names), so anything that could escape it is rejected.
+    fn folder_name(&self) -> Result<String, CommandError> {
+        let concept = self.concept_name.trim();
+        if concept.is_empty() {
+            return Err(CommandError::invalid_input("Kohya concept name cannot be empty"));
+        }
+        if concept.contains("..")
+            || concept
+                .chars()
+                .any(|c| matches!(c, '/' | '\\' | ':') || c.is_control())
+        {
+            return Err(CommandError::invalid_input(format!(
+                "Invalid Kohya concept name \"{}\": it cannot contain path separators, \"..\", \":\" or control characters",
+                concept
+            )));
+        }
+        Ok(format!("{}_{}", self.repeat_count.max(1), concept))
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ExportOptions {
     pub source_path: String,
@@ -38,6 +102,155 @@ pub struct ExportOptions {
     pub trigger_word: Option<String>,
     #[serde(default)]
     pub sequential_naming: bool,
+    /// When exporting the whole source (no relative_paths), include images in subfolders (default).
+    #[serde(default = "default_true")]
+    pub recursive: bool,
+    /// Folder-name patterns to skip (see ScanIgnore); None skips hidden, originals and
+    /// caption_backups folders. `.lora-studio` is always skipped.
+    #[serde(default)]
+    pub ignore_patterns: Option<Vec<String>>,
+    /// Estimate the export size (images plus captions) and fail before writing anything if the
+    /// destination disk doesn't have that much free space.
+    #[serde(default)]
+    pub check_free_space: bool,
+    /// Mirror the source subfolder tree in the export (folders, or entry paths in ZIP/tar)
+    /// instead of flattening everything into one directory.
+    #[serde(default)]
+    pub preserve_structure: bool,
+    /// Write WebDataset-style .tar archives instead of a folder or ZIP. Samples are keyed
+    /// `0000000`, `0000001`, ... (`<key>.<image ext>` + `<key>.<caption ext>`), so sequential_naming
+    /// and preserve_structure don't apply. Only the txt caption format is supported.
+    #[serde(default)]
+    pub as_tar: bool,
+    /// Max samples per .tar shard. When set, dest_path is a folder and shards are written as
+    /// dataset-000000.tar, dataset-000001.tar, ...; when unset, a single .tar is written to dest_path.
+    #[serde(default)]
+    pub shard_size: Option<usize>,
+    #[serde(default)]
+    pub caption_format: CaptionFormat,
+    /// If set, export into a Kohya `N_concept/` subfolder (folder and ZIP exports).
+    #[serde(default)]
+    pub kohya_folder: Option<KohyaFolderOptions>,
+    /// Multi-concept Kohya export: each concept's images go into its own `N_concept/` folder in one
+    /// destination (folder and ZIP exports). Replaces relative_paths and kohya_folder.
+    #[serde(default)]
+    pub kohya_concepts: Option<Vec<KohyaConcept>>,
+    /// Shuffle tag order in each exported caption (folder and ZIP exports).
+    #[serde(default)]
+    pub shuffle_tags: bool,
+    /// Fraction of tags (0.0-1.0) randomly dropped from each exported caption (folder and ZIP exports).
+    #[serde(default)]
+    pub tag_dropout: Option<f32>,
+    /// Leading tags that are never shuffled or dropped (e.g. character/priority tags).
+    #[serde(default)]
+    pub keep_first_n: Option<usize>,
+    /// Tags moved to the front of each exported caption in this order (case-insensitive), right
+    /// after the trigger word. The rest keep their order; priority tags are never shuffled or dropped.
+    #[serde(default)]
+    pub priority_tags: Option<Vec<String>>,
+    /// Seed for shuffle/dropout; the same seed reproduces the same captions.
+    #[serde(default)]
+    pub augment_seed: Option<u64>,
+    /// Also write an sd-scripts `dataset.toml` describing the exported folders (folder and ZIP exports).
+    #[serde(default)]
+    pub emit_kohya_toml: bool,
+    /// Training resolution written to dataset.toml.
+    #[serde(default)]
+    pub kohya_resolution: Option<u32>,
+    /// Also write manifest.json listing each exported image with its source path, destination,
+    /// SHA-256 and caption (folder and ZIP exports).
+    #[serde(default)]
+    pub emit_manifest: bool,
+    /// What to do when a destination file already exists (folder export only).
+    #[serde(default)]
+    pub overwrite: OverwritePolicy,
+    /// Only export images whose caption has all of these tags (case-insensitive).
+    #[serde(default)]
+    pub require_tags: Option<Vec<String>>,
+    /// Skip images whose caption has any of these tags (case-insensitive).
+    #[serde(default)]
+    pub exclude_tags: Option<Vec<String>>,
+    /// Resolution constraints, checked from image headers; failing images count as skipped.
+    #[serde(default)]
+    pub min_width: Option<u32>,
+    #[serde(default)]
+    pub min_height: Option<u32>,
+    #[serde(default)]
+    pub min_megapixels: Option<f64>,
+    /// Allowed width/height ratios (e.g. 1.0, 0.75, 1.333); others are skipped.
+    #[serde(default)]
+    pub allowed_aspects: Option<Vec<f64>>,
+    /// Relative tolerance for allowed_aspects (default 0.05).
+    #[serde(default)]
+    pub aspect_tolerance: Option<f64>,
+}
+
+const DEFAULT_ASPECT_TOLERANCE: f64 = 0.05;
+
+impl ExportOptions {
+    fn has_dimension_constraints(&self) -> bool {
+        self.min_width.is_some()
+            || self.min_height.is_some()
+            || self.min_megapixels.is_some()
+            || self.allowed_aspects.as_ref().is_some_and(|a| !a.is_empty())
+    }
+
+    /// Why an image fails the resolution constraints, or None if it passes.
+    fn dimension_rejection(&self, img: &Path) -> Option<&'static str> {
+        let Some((w, h)) = ImageReader::open(img)
+            .ok()
+            .and_then(|r| r.into_dimensions().ok())
+            .filter(|&(w, h)| w > 0 && h > 0)
+        else {
+            return Some("unreadable");
+        };
+        if self.min_width.is_some_and(|min| w < min) || self.min_height.is_some_and(|min| h < min) {
+            return Some("too_small");
+        }
+        if self
+            .min_megapixels
+            .is_some_and(|min| (w as f64 * h as f64) / 1_000_000.0 < min)
+        {
+            return Some("too_few_megapixels");
+        }
+        if let Some(aspects) = self.allowed_aspects.as_ref().filter(|a| !a.is_empty()) {
+            let tolerance = self.aspect_tolerance.unwrap_or(DEFAULT_ASPECT_TOLERANCE);
+            let ratio = w as f64 / h as f64;
+            if !aspects
+                .iter()
+                .any(|&a| a > 0.0 && ((ratio - a).abs() / a) <= tolerance)
+            {
+                return Some("aspect");
+            }
+        }
+        None
+    }
+}
+
+/// Overwrite behavior for files that already exist in the destination folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverwritePolicy {
+    #[default]
+    Always,
+    /// Leave existing destination files untouched.
+    SkipExisting,
+    /// Copy only when the source was modified after the destination.
+    IfNewer,
+}
+
+impl OverwritePolicy {
+    fn should_write(self, src: &Path, dest: &Path) -> bool {
+        let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+        match self {
+            OverwritePolicy::Always => true,
+            OverwritePolicy::SkipExisting => !dest.exists(),
+            OverwritePolicy::IfNewer => match (modified(src), modified(dest)) {
+                (Some(src_time), Some(dest_time)) => src_time > dest_time,
+                _ => true,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -47,6 +260,53 @@ pub struct ExportResult {
     pub skipped_count: usize,
     pub error: Option<String>,
     pub output_path: String,
+    /// Number of .tar shards written (tar export only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard_count: Option<usize>,
+    /// Images left as-is because the destination already had them (overwrite policy).
+    pub skipped_existing_count: usize,
+    /// Images dropped by the resolution constraints, by reason ("too_small", "too_few_megapixels",
+    /// "aspect", "unreadable"); these are included in skipped_count.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_reasons: Option<HashMap<String, usize>>,
+    /// Exported count per Kohya concept folder (kohya_concepts exports only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concept_counts: Option<Vec<ConceptExportCount>>,
+    /// Images that could not be read or written, with the error (included in skipped_count).
+    pub skipped: Vec<SkippedImage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedImage {
+    pub relative_path: String,
+    pub reason: String,
+}
+
+impl SkippedImage {
+    fn new(img: &Path, source_root: &Path, reason: String) -> Self {
+        Self {
+            relative_path: img
+                .strip_prefix(source_root)
+                .unwrap_or(img)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            reason,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConceptExportCount {
+    pub folder: String,
+    pub exported_count: usize,
+}
+
+/// Images exported into one (optional) subfolder of the destination.
+struct ExportGroup {
+    subfolder: Option<String>,
+    /// Kohya repeat count of the subfolder (1 without the Kohya layout).
+    repeats: u32,
+    images: Vec<PathBuf>,
 }
 
 /// Normalize relative path: forward slashes, trim leading slashes.
@@ -60,51 +320,186 @@ fn normalize_key_for_lookup(s: &str) -> String {
 }
 
 #[tauri::command]
-pub async fn export_dataset(options: ExportOptions) -> Result<ExportResult, String> {
+pub async fn export_dataset(options: ExportOptions) -> Result<ExportResult, CommandError> {
     let source = PathBuf::from(&options.source_path);
     if !source.is_dir() {
-        return Err("Source folder does not exist".to_string());
+        return Err(CommandError::not_found("Source folder does not exist"));
     }
-    let canonical_source = source.canonicalize().map_err(|e| e.to_string())?;
+    let canonical_source = source.canonicalize()?;
+    let caption_ext = caption_extension(&canonical_source);
 
-    let mut images: Vec<PathBuf> = Vec::new();
+    let tag_set = |tags: &Option<Vec<String>>| -> HashSet<String> {
+        tags.iter()
+            .flatten()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect()
+    };
+    let required = tag_set(&options.require_tags);
+    let excluded = tag_set(&options.exclude_tags);
+    let include = |full: &Path| {
+        let cap = caption_path(full, &caption_ext);
+        if options.only_captioned && !cap.exists() {
+            return false;
+        }
+        if required.is_empty() && excluded.is_empty() {
+            return true;
+        }
+        let tags: HashSet<String> = read_caption_text(&cap)
+            .unwrap_or_default()
+            .split(',')
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        required.is_subset(&tags) && excluded.is_disjoint(&tags)
+    };
+    // Resolution constraints run last (they read image headers) and count what they drop.
+    let rejections: RefCell<HashMap<String, usize>> = RefCell::default();
+    let check_dimensions = options.has_dimension_constraints();
+    let include = |full: &Path| {
+        if !include(full) {
+            return false;
+        }
+        if check_dimensions {
+            if let Some(reason) = options.dimension_rejection(full) {
+                *rejections.borrow_mut().entry(reason.to_string()).or_default() += 1;
+                return false;
+            }
+        }
+        true
+    };
+    // Frontend paths are used directly: join each to canonical source and keep it if the file exists.
+    let from_relative = |relative_paths: &[String]| -> Vec<PathBuf> {
+        let mut images: Vec<PathBuf> = relative_paths
+            .iter()
+            .map(|rel| normalize_rel(rel))
+            .filter(|normalized| !normalized.is_empty())
+            .map(|normalized| canonical_source.join(normalized))
+            .filter(|full| full.is_file() && is_image(full) && include(full))
+            .collect();
+        images.sort();
+        images
+    };
 
-    if let Some(ref relative_paths) = options.relative_paths {
-        // Use frontend paths directly: join each to canonical source and add if file exists
-        for rel in relative_paths {
-            let normalized = normalize_rel(rel);
-            if normalized.is_empty() {
-                continue;
+    if options.as_tar {
+        if options.kohya_folder.is_some() {
+            return Err(CommandError::invalid_input("Kohya folders are not supported for tar export"));
+        }
+        if options.caption_format != CaptionFormat::Txt {
+            return Err(CommandError::invalid_input(
+                "Tar export writes one caption per sample; metadata, CSV and JSONL caption formats are not supported",
+            ));
+        }
+    }
+
+    let groups: Vec<ExportGroup> = if let Some(concepts) = &options.kohya_concepts {
+        if options.as_tar {
+            return Err(CommandError::invalid_input("Kohya concepts are not supported for tar export"));
+        }
+        if options.kohya_folder.is_some() {
+            return Err(CommandError::invalid_input("Use either kohya_folder or kohya_concepts, not both"));
+        }
+        // Validate every concept name before any images are grouped.
+        let folders = concepts
+            .iter()
+            .map(|concept| concept.folder.folder_name())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut seen_paths = HashSet::new();
+        let mut seen_folders = HashSet::new();
+        let mut groups = Vec::new();
+        for (concept, folder) in concepts.iter().zip(folders) {
+            if !seen_folders.insert(folder.to_lowercase()) {
+                return Err(CommandError::invalid_input(format!("Duplicate Kohya concept folder: {}", folder)));
             }
-            let full = canonical_source.join(&normalized);
-            if full.is_file() && is_image(&full) {
-                if options.only_captioned && !caption_path(&full).exists() {
-                    continue;
+            for rel in &concept.relative_paths {
+                if !seen_paths.insert(normalize_key_for_lookup(rel)) {
+                    return Err(CommandError::invalid_input(format!("{} is assigned to more than one Kohya concept", rel)));
                 }
-                images.push(full);
             }
+            groups.push(ExportGroup {
+                subfolder: Some(folder),
+                repeats: concept.folder.repeat_count.max(1),
+                images: from_relative(&concept.relative_paths),
+            });
         }
+        groups
     } else {
-        // No filter: walk entire source and add all (subject to only_captioned)
-        for entry in WalkDir::new(&canonical_source).follow_links(false).into_iter().filter_map(Result::ok) {
-            let p = entry.path();
-            if !p.is_file() || !is_image(p) {
-                continue;
-            }
-            if options.only_captioned && !caption_path(p).exists() {
-                continue;
+        let images = match &options.relative_paths {
+            Some(relative_paths) => from_relative(relative_paths),
+            None => {
+                // No filter: walk entire source and add all (subject to only_captioned)
+                let mut images = Vec::new();
+                let max_depth = if options.recursive { usize::MAX } else { 1 };
+                let ignore = ScanIgnore::new(options.ignore_patterns.as_deref());
+                for entry in WalkDir::new(&canonical_source)
+                    .max_depth(max_depth)
+                    .follow_links(false)
+                    .into_iter()
+                    .filter_entry(|e| ignore.allows(e))
+                    .filter_map(Result::ok)
+                {
+                    let p = entry.path();
+                    if p.is_file() && is_image(p) && include(p) {
+                        images.push(p.to_path_buf());
+                    }
+                }
+                images.sort();
+                images
             }
-            images.push(p.to_path_buf());
-        }
-    }
+        };
+        let subfolder = options.kohya_folder.as_ref().map(|k| k.folder_name()).transpose()?;
+        let repeats = options.kohya_folder.as_ref().map_or(1, |k| k.repeat_count.max(1));
+        vec![ExportGroup {
+            subfolder,
+            repeats,
+            images,
+        }]
+    };
 
-    images.sort();
+    if options.check_free_space {
+        check_free_space(&groups, &options, &caption_ext)?;
+    }
 
-    if options.as_zip {
-        export_zip(&images, &options)
+    let mut result = if options.as_tar {
+        export_tar(&groups[0].images, &options)
+    } else if options.as_zip {
+        export_zip(&groups, &options)
     } else {
-        export_folder(&images, &options)
+        export_folder(&groups, &options)
+    }?;
+    let rejections = rejections.into_inner();
+    if !rejections.is_empty() {
+        result.skipped_count += rejections.values().sum::<usize>();
+        result.skip_reasons = Some(rejections);
     }
+    Ok(result)
+}
+
+/// Source bytes of the images and their captions; ZIP output is usually smaller.
+fn estimate_export_bytes(groups: &[ExportGroup], caption_ext: &str) -> u64 {
+    let file_len = |p: &Path| fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+    groups
+        .iter()
+        .flat_map(|g| &g.images)
+        .map(|img| file_len(img) + file_len(&caption_path(img, caption_ext)))
+        .sum()
+}
+
+fn check_free_space(groups: &[ExportGroup], opt: &ExportOptions, caption_ext: &str) -> Result<(), String> {
+    let needed = estimate_export_bytes(groups, caption_ext);
+    let Some(disk) = disk_stats_for(Path::new(&opt.dest_path)) else {
+        return Ok(());
+    };
+    if needed > disk.free_bytes {
+        const MB: f64 = 1024.0 * 1024.0;
+        return Err(format!(
+            "Not enough free space: the export needs about {:.0} MB but only {:.0} MB is free on {}",
+            needed as f64 / MB,
+            disk.free_bytes as f64 / MB,
+            disk.mount_point
+        ));
+    }
+    Ok(())
 }
 
 fn apply_trigger(content: &str, trigger: Option<&String>) -> String {
@@ -115,49 +510,449 @@ fn apply_trigger(content: &str, trigger: Option<&String>) -> String {
     }
 }
 
-fn export_folder(images: &[PathBuf], opt: &ExportOptions) -> Result<ExportResult, String> {
-    let dest = PathBuf::from(&opt.dest_path);
-    fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+/// Output path for the i-th exported image: 0001.ext when sequential, else the original filename.
+/// With preserve_structure it is prefixed by the image's folder relative to the source root
+/// ("character_a/0001.png"), always with forward slashes.
+fn export_name(i: usize, img: &Path, opt: &ExportOptions, source_root: &Path) -> String {
+    let ext = img.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let name = if opt.sequential_naming {
+        format!("{:04}.{}", i + 1, ext)
+    } else {
+        img.file_name().and_then(|n| n.to_str()).unwrap_or("image.png").to_string()
+    };
+    if !opt.preserve_structure {
+        return name;
+    }
+    let folder = img
+        .parent()
+        .and_then(|p| p.strip_prefix(source_root).ok())
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default();
+    entry_path(Some(folder.as_str()).filter(|f| !f.is_empty()), &name)
+}
+
+/// Small seeded PRNG (SplitMix64) so caption augmentation is reproducible without extra deps.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// FNV-1a, used to give each image its own RNG stream independent of export order.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xCBF2_9CE4_8422_2325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+/// Move tags listed in priority_tags to the front, in that order. Returns the reordered tags and
+/// how many of them are priority tags.
+fn prioritize_tags<'a>(tags: Vec<&'a str>, priority: &[String]) -> (Vec<&'a str>, usize) {
+    let mut rest = tags;
+    let mut ordered = Vec::with_capacity(rest.len());
+    for p in priority.iter().map(|p| p.trim()) {
+        if let Some(pos) = rest.iter().position(|t| t.eq_ignore_ascii_case(p)) {
+            ordered.push(rest.remove(pos));
+        }
+    }
+    let count = ordered.len();
+    ordered.extend(rest);
+    (ordered, count)
+}
+
+/// Apply priority_tags, then shuffle_tags / tag_dropout to a caption, keeping the priority tags
+/// and the next keep_first_n tags in place. Runs before the trigger word is prepended, so the
+/// trigger is never dropped.
+fn augment_caption(content: &str, img: &Path, opt: &ExportOptions) -> String {
+    let dropout = opt.tag_dropout.unwrap_or(0.0).clamp(0.0, 1.0);
+    let priority = opt.priority_tags.as_deref().unwrap_or(&[]);
+    if !opt.shuffle_tags && dropout <= 0.0 && priority.is_empty() {
+        return content.trim().to_string();
+    }
+    let tags: Vec<&str> = content
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect();
+    let (tags, priority_count) = prioritize_tags(tags, priority);
+    let keep = (priority_count + opt.keep_first_n.unwrap_or(0)).min(tags.len());
+    let (kept, rest) = tags.split_at(keep);
+
+    let key = img.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let mut rng = SplitMix64(opt.augment_seed.unwrap_or(0) ^ fnv1a(key));
+    let mut rest: Vec<&str> = rest
+        .iter()
+        .copied()
+        .filter(|_| rng.next_f32() >= dropout)
+        .collect();
+    if opt.shuffle_tags {
+        for i in (1..rest.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            rest.swap(i, j);
+        }
+    }
+    kept.iter().chain(rest.iter()).copied().collect::<Vec<_>>().join(", ")
+}
+
+/// Read an image's caption (if any) with tag augmentation and the trigger word applied.
+fn export_caption(img: &Path, caption_ext: &str, opt: &ExportOptions) -> Option<String> {
+    let cap_src = caption_path(img, caption_ext);
+    if !cap_src.exists() {
+        return None;
+    }
+    read_caption_text(&cap_src).ok().map(|content| {
+        apply_trigger(&augment_caption(&content, img, opt), opt.trigger_word.as_ref())
+    })
+}
+
+/// Path of an entry inside the export, under the optional Kohya subfolder. Always uses forward slashes.
+fn entry_path(subfolder: Option<&str>, name: &str) -> String {
+    match subfolder {
+        Some(sub) => format!("{}/{}", sub, name),
+        None => name.to_string(),
+    }
+}
+
+fn metadata_json(metadata: serde_json::Map<String, serde_json::Value>) -> Result<String, String> {
+    serde_json::to_string_pretty(&serde_json::Value::Object(metadata)).map_err(|e| e.to_string())
+}
+
+/// One metadata.jsonl line. Images without a caption get empty text, since imagefolder expects
+/// a row for every image.
+fn jsonl_line(file_name: &str, caption: Option<&str>) -> String {
+    let row = serde_json::json!({ "file_name": file_name, "text": caption.unwrap_or("") });
+    format!("{}\n", row)
+}
+
+const CAPTIONS_CSV_HEADER: &str = "filename,relative_path,caption,rating,width,height\n";
+
+/// Quote a CSV field when it contains a comma, quote, or line break (RFC 4180).
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Source-side data needed for the relative_path and rating columns of captions.csv.
+struct CsvContext {
+    canonical_source: PathBuf,
+    project_root: String,
+    ratings: RatingsData,
+}
+
+impl CsvContext {
+    fn new(opt: &ExportOptions) -> Result<Self, String> {
+        let canonical_source = PathBuf::from(&opt.source_path)
+            .canonicalize()
+            .map_err(|e| e.to_string())?;
+        let project_root = canonical_source
+            .to_str()
+            .unwrap_or(opt.source_path.as_str())
+            .to_string();
+        let ratings = load_ratings(&project_root);
+        Ok(Self {
+            canonical_source,
+            project_root,
+            ratings,
+        })
+    }
+
+    /// One captions.csv line for an exported image. Dimensions come from the header only.
+    fn row(&self, img: &Path, name: &str, caption: &str) -> String {
+        let rel = img
+            .strip_prefix(&self.canonical_source)
+            .ok()
+            .and_then(|r| r.to_str())
+            .map(|s| s.replace('\\', "/"))
+            .unwrap_or_default();
+        let rel_key = normalize_rel(&rel);
+        let rating = get_rating_for_path(&self.ratings, &rel_key, &rel, &self.project_root);
+        let (width, height) = ImageReader::open(img)
+            .ok()
+            .and_then(|r| r.into_dimensions().ok())
+            .map(|(w, h)| (w.to_string(), h.to_string()))
+            .unwrap_or_default();
+        format!(
+            "{},{},{},{},{},{}\n",
+            csv_field(name),
+            csv_field(&rel_key),
+            csv_field(caption),
+            rating,
+            width,
+            height
+        )
+    }
+}
+
+/// TOML string: a literal string unless it contains a single quote or control characters.
+fn toml_string(s: &str) -> String {
+    if s.contains('\'') || s.chars().any(char::is_control) {
+        let escaped: String = s
+            .chars()
+            .map(|c| match c {
+                '"' => "\\\"".to_string(),
+                '\\' => "\\\\".to_string(),
+                c if c.is_control() => format!("\\u{:04X}", c as u32),
+                c => c.to_string(),
+            })
+            .collect();
+        format!("\"{}\"", escaped)
+    } else {
+        format!("'{}'", s)
+    }
+}
+
+/// sd-scripts dataset config with one `[[datasets.subsets]]` per exported folder.
+/// `image_root` is the absolute export folder; None writes image_dir relative to the archive root.
+fn kohya_dataset_toml(groups: &[ExportGroup], opt: &ExportOptions, image_root: Option<&Path>) -> String {
+    let caption_ext = caption_extension(Path::new(&opt.source_path));
+    let trigger = opt
+        .trigger_word
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+
+    let mut toml = String::from("[general]\n");
+    toml.push_str(&format!("caption_extension = {}\n", toml_string(&format!(".{}", caption_ext))));
+    toml.push_str(&format!("shuffle_caption = {}\n", opt.shuffle_tags));
+    if trigger.is_some() {
+        // The trigger word is prepended to every caption; keep it in place when shuffling.
+        toml.push_str("keep_tokens = 1\n");
+    }
+    toml.push_str("\n[[datasets]]\n");
+    if let Some(resolution) = opt.kohya_resolution {
+        toml.push_str(&format!("resolution = {}\n", resolution));
+    }
+    for group in groups {
+        let image_dir = match (image_root, group.subfolder.as_deref()) {
+            (Some(root), Some(sub)) => root.join(sub).to_string_lossy().into_owned(),
+            (Some(root), None) => root.to_string_lossy().into_owned(),
+            (None, Some(sub)) => sub.to_string(),
+            (None, None) => ".".to_string(),
+        };
+        toml.push_str("\n  [[datasets.subsets]]\n");
+        toml.push_str(&format!("  image_dir = {}\n", toml_string(&image_dir)));
+        toml.push_str(&format!("  num_repeats = {}\n", group.repeats));
+        if let Some(t) = trigger {
+            toml.push_str(&format!("  class_tokens = {}\n", toml_string(t)));
+        }
+    }
+    toml
+}
+
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    /// Relative path in the source project.
+    source: String,
+    /// Path inside the export.
+    destination: String,
+    sha256: String,
+    caption: Option<String>,
+}
+
+fn manifest_entry(
+    img: &Path,
+    source_root: &Path,
+    destination: String,
+    sha256: String,
+    caption: Option<&String>,
+) -> ManifestEntry {
+    ManifestEntry {
+        source: normalize_rel(&img.strip_prefix(source_root).unwrap_or(img).to_string_lossy()),
+        destination,
+        sha256,
+        caption: caption.cloned(),
+    }
+}
+
+fn manifest_json(entries: &[ManifestEntry]) -> Result<String, String> {
+    serde_json::to_string_pretty(&serde_json::json!({ "files": entries })).map_err(|e| e.to_string())
+}
+
+/// Copy a file while hashing it, so the manifest needs no second read.
+fn copy_with_sha256(src: &Path, dest: &Path) -> std::io::Result<String> {
+    use std::io::{Read, Write};
+    let mut reader = fs::File::open(src)?;
+    let mut writer = std::io::BufWriter::new(fs::File::create(dest)?);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+    }
+    writer.flush()?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Per-concept counts for the result; None unless this is a kohya_concepts export.
+fn concept_counts(
+    groups: &[ExportGroup],
+    counts: Vec<usize>,
+    opt: &ExportOptions,
+) -> Option<Vec<ConceptExportCount>> {
+    opt.kohya_concepts.as_ref()?;
+    Some(
+        groups
+            .iter()
+            .zip(counts)
+            .map(|(group, exported_count)| ConceptExportCount {
+                folder: group.subfolder.clone().unwrap_or_default(),
+                exported_count,
+            })
+            .collect(),
+    )
+}
+
+fn export_folder(groups: &[ExportGroup], opt: &ExportOptions) -> Result<ExportResult, String> {
+    let caption_ext = caption_extension(Path::new(&opt.source_path));
+    let root = PathBuf::from(&opt.dest_path);
+    fs::create_dir_all(&root).map_err(|e| e.to_string())?;
+
+    let csv_ctx = (opt.caption_format == CaptionFormat::Csv)
+        .then(|| CsvContext::new(opt))
+        .transpose()?;
 
     let mut exported = 0usize;
     let mut skipped = 0usize;
+    let mut failures = Vec::new();
+    let mut skipped_existing = 0usize;
+    let mut counts = Vec::with_capacity(groups.len());
+    let mut metadata = serde_json::Map::new();
+    let mut csv = String::from(CAPTIONS_CSV_HEADER);
+    let mut jsonl = String::new();
+    let mut manifest = Vec::new();
+    let source_root = PathBuf::from(&opt.source_path);
+    let source_root = source_root.canonicalize().unwrap_or(source_root);
 
-    for (i, img) in images.iter().enumerate() {
-        let ext = img.extension().and_then(|e| e.to_str()).unwrap_or("png");
-        let name = if opt.sequential_naming {
-            format!("{:04}.{}", i + 1, ext)
-        } else {
-            img.file_name().and_then(|n| n.to_str()).unwrap_or("image.png").to_string()
+    for group in groups {
+        let subfolder = group.subfolder.as_deref();
+        let dest = match subfolder {
+            Some(sub) => root.join(sub),
+            None => root.clone(),
         };
+        fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+        let mut group_exported = 0usize;
 
-        let dest_img = dest.join(&name);
-        if fs::copy(img, &dest_img).is_err() {
-            skipped += 1;
-            continue;
-        }
+        for (i, img) in group.images.iter().enumerate() {
+            let name = export_name(i, img, opt, &source_root);
+
+            let dest_img = dest.join(&name);
+            if let Some(parent) = dest_img.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let copy_image = opt.overwrite.should_write(img, &dest_img);
+            let sha256 = match (copy_image, opt.emit_manifest) {
+                (true, true) => copy_with_sha256(img, &dest_img),
+                (true, false) => fs::copy(img, &dest_img).map(|_| String::new()),
+                // Kept file: still listed in the manifest and caption files below.
+                (false, true) => fs::read(&dest_img).map(|d| hex::encode(Sha256::digest(d))),
+                (false, false) => Ok(String::new()),
+            };
+            let sha256 = match sha256 {
+                Ok(sha256) => sha256,
+                Err(e) => {
+                    skipped += 1;
+                    failures.push(SkippedImage::new(img, &source_root, format!("Copy failed: {}", e)));
+                    continue;
+                }
+            };
 
-        let base = name.rsplit_once('.').map(|(n, _)| n).unwrap_or(&name);
-        let dest_txt = dest.join(format!("{}.txt", base));
-        let cap_src = caption_path(img);
-        if cap_src.exists() {
-            if let Ok(content) = fs::read_to_string(&cap_src) {
-                let out = apply_trigger(&content, opt.trigger_word.as_ref());
-                let _ = fs::write(&dest_txt, out);
+            let base = name.rsplit_once('.').map(|(n, _)| n).unwrap_or(&name);
+            let caption = export_caption(img, &caption_ext, opt);
+            if opt.emit_manifest {
+                let entry = entry_path(subfolder, &name);
+                manifest.push(manifest_entry(img, &source_root, entry, sha256, caption.as_ref()));
+            }
+            match (opt.caption_format, caption) {
+                (CaptionFormat::Txt, Some(out)) => {
+                    let dest_txt = dest.join(format!("{}.{}", base, caption_ext));
+                    if opt.overwrite.should_write(&caption_path(img, &caption_ext), &dest_txt) {
+                        let _ = fs::write(dest_txt, out);
+                    }
+                }
+                (CaptionFormat::Metadata, Some(out)) => {
+                    metadata.insert(
+                        entry_path(subfolder, base),
+                        serde_json::json!({ "caption": out }),
+                    );
+                }
+                (CaptionFormat::Csv, caption) => {
+                    if let Some(ctx) = &csv_ctx {
+                        let entry = entry_path(subfolder, &name);
+                        csv.push_str(&ctx.row(img, &entry, caption.as_deref().unwrap_or("")));
+                    }
+                }
+                (CaptionFormat::Jsonl, caption) => {
+                    jsonl.push_str(&jsonl_line(&entry_path(subfolder, &name), caption.as_deref()));
+                }
+                _ => {}
+            }
+            if copy_image {
+                exported += 1;
+                group_exported += 1;
+            } else {
+                skipped_existing += 1;
             }
         }
-        exported += 1;
+        counts.push(group_exported);
+    }
+
+    match opt.caption_format {
+        CaptionFormat::Metadata => {
+            fs::write(root.join("metadata.json"), metadata_json(metadata)?)
+                .map_err(|e| e.to_string())?;
+        }
+        CaptionFormat::Csv => {
+            fs::write(root.join("captions.csv"), csv).map_err(|e| e.to_string())?;
+        }
+        CaptionFormat::Jsonl => {
+            fs::write(root.join("metadata.jsonl"), jsonl).map_err(|e| e.to_string())?;
+        }
+        CaptionFormat::Txt => {}
+    }
+
+    if opt.emit_manifest {
+        fs::write(root.join("manifest.json"), manifest_json(&manifest)?)
+            .map_err(|e| e.to_string())?;
+    }
+    if opt.emit_kohya_toml {
+        let image_root = std::path::absolute(&root).unwrap_or_else(|_| root.clone());
+        fs::write(root.join("dataset.toml"), kohya_dataset_toml(groups, opt, Some(&image_root)))
+            .map_err(|e| e.to_string())?;
     }
 
     Ok(ExportResult {
         success: true,
         exported_count: exported,
         skipped_count: skipped,
+        skipped_existing_count: skipped_existing,
+        skip_reasons: None,
         error: None,
         output_path: opt.dest_path.clone(),
+        shard_count: None,
+        concept_counts: concept_counts(groups, counts, opt),
+        skipped: failures,
     })
 }
 
-fn export_zip(images: &[PathBuf], opt: &ExportOptions) -> Result<ExportResult, String> {
+fn export_zip(groups: &[ExportGroup], opt: &ExportOptions) -> Result<ExportResult, String> {
     use std::io::Write;
 
     let file = fs::File::create(&opt.dest_path).map_err(|e| e.to_string())?;
@@ -165,48 +960,225 @@ fn export_zip(images: &[PathBuf], opt: &ExportOptions) -> Result<ExportResult, S
     let opts = zip::write::SimpleFileOptions::default()
         .compression_method(zip::CompressionMethod::Deflated);
 
+    let caption_ext = caption_extension(Path::new(&opt.source_path));
+    let csv_ctx = (opt.caption_format == CaptionFormat::Csv)
+        .then(|| CsvContext::new(opt))
+        .transpose()?;
+
     let mut exported = 0usize;
     let mut skipped = 0usize;
+    let mut failures = Vec::new();
+    let mut counts = Vec::with_capacity(groups.len());
+    let mut metadata = serde_json::Map::new();
+    let mut csv = String::from(CAPTIONS_CSV_HEADER);
+    let mut jsonl = String::new();
+    let mut manifest = Vec::new();
+    let source_root = PathBuf::from(&opt.source_path);
+    let source_root = source_root.canonicalize().unwrap_or(source_root);
 
-    for (i, img) in images.iter().enumerate() {
-        let ext = img.extension().and_then(|e| e.to_str()).unwrap_or("png");
-        let name = if opt.sequential_naming {
-            format!("{:04}.{}", i + 1, ext)
-        } else {
-            img.file_name().and_then(|n| n.to_str()).unwrap_or("image.png").to_string()
-        };
+    for group in groups {
+        let subfolder = group.subfolder.as_deref();
+        let mut group_exported = 0usize;
+
+        for (i, img) in group.images.iter().enumerate() {
+            let name = export_name(i, img, opt, &source_root);
+
+            let data = match fs::read(img) {
+                Ok(d) => d,
+                Err(e) => {
+                    skipped += 1;
+                    failures.push(SkippedImage::new(img, &source_root, format!("Read failed: {}", e)));
+                    continue;
+                }
+            };
+            let written = zip
+                .start_file(entry_path(subfolder, &name), opts)
+                .map_err(|e| e.to_string())
+                .and_then(|_| zip.write_all(&data).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                skipped += 1;
+                failures.push(SkippedImage::new(img, &source_root, format!("Write failed: {}", e)));
+                continue;
+            }
 
+            let base = name.rsplit_once('.').map(|(n, _)| n).unwrap_or(&name);
+            let caption = export_caption(img, &caption_ext, opt);
+            if opt.emit_manifest {
+                let sha256 = hex::encode(Sha256::digest(&data));
+                let entry = entry_path(subfolder, &name);
+                manifest.push(manifest_entry(img, &source_root, entry, sha256, caption.as_ref()));
+            }
+            match (opt.caption_format, caption) {
+                (CaptionFormat::Txt, Some(out)) => {
+                    let txt_name = entry_path(subfolder, &format!("{}.{}", base, caption_ext));
+                    zip.start_file(txt_name, opts).map_err(|e| e.to_string())?;
+                    zip.write_all(out.as_bytes()).map_err(|e| e.to_string())?;
+                }
+                (CaptionFormat::Metadata, Some(out)) => {
+                    metadata.insert(
+                        entry_path(subfolder, base),
+                        serde_json::json!({ "caption": out }),
+                    );
+                }
+                (CaptionFormat::Csv, caption) => {
+                    if let Some(ctx) = &csv_ctx {
+                        let entry = entry_path(subfolder, &name);
+                        csv.push_str(&ctx.row(img, &entry, caption.as_deref().unwrap_or("")));
+                    }
+                }
+                (CaptionFormat::Jsonl, caption) => {
+                    jsonl.push_str(&jsonl_line(&entry_path(subfolder, &name), caption.as_deref()));
+                }
+                _ => {}
+            }
+            exported += 1;
+            group_exported += 1;
+        }
+        counts.push(group_exported);
+    }
+
+    match opt.caption_format {
+        CaptionFormat::Metadata => {
+            zip.start_file("metadata.json", opts).map_err(|e| e.to_string())?;
+            zip.write_all(metadata_json(metadata)?.as_bytes())
+                .map_err(|e| e.to_string())?;
+        }
+        CaptionFormat::Csv => {
+            zip.start_file("captions.csv", opts).map_err(|e| e.to_string())?;
+            zip.write_all(csv.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        CaptionFormat::Jsonl => {
+            zip.start_file("metadata.jsonl", opts).map_err(|e| e.to_string())?;
+            zip.write_all(jsonl.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        CaptionFormat::Txt => {}
+    }
+
+    if opt.emit_manifest {
+        zip.start_file("manifest.json", opts).map_err(|e| e.to_string())?;
+        zip.write_all(manifest_json(&manifest)?.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    if opt.emit_kohya_toml {
+        zip.start_file("dataset.toml", opts).map_err(|e| e.to_string())?;
+        zip.write_all(kohya_dataset_toml(groups, opt, None).as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(ExportResult {
+        success: true,
+        exported_count: exported,
+        skipped_count: skipped,
+        skipped_existing_count: 0,
+        skip_reasons: None,
+        error: None,
+        output_path: opt.dest_path.clone(),
+        shard_count: None,
+        concept_counts: concept_counts(groups, counts, opt),
+        skipped: failures,
+    })
+}
+
+/// Append one in-memory file to a tar archive.
+fn append_tar_entry<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    );
+    tar.append_data(&mut header, name, data).map_err(|e| e.to_string())
+}
+
+fn open_tar_shard(path: &Path) -> Result<tar::Builder<fs::File>, String> {
+    let file = fs::File::create(path).map_err(|e| e.to_string())?;
+    Ok(tar::Builder::new(file))
+}
+
+/// WebDataset export: each sample is <key>.<ext> + <key>.<caption ext>, stored next to each other in the archive.
+/// With shard_size, rolls over into dataset-000000.tar, dataset-000001.tar, ... inside dest_path.
+fn export_tar(images: &[PathBuf], opt: &ExportOptions) -> Result<ExportResult, String> {
+    let shard_size = opt.shard_size.filter(|&n| n > 0);
+    let shard_path = |index: usize| -> PathBuf {
+        match shard_size {
+            Some(_) => PathBuf::from(&opt.dest_path).join(format!("dataset-{:06}.tar", index)),
+            None => PathBuf::from(&opt.dest_path),
+        }
+    };
+    if shard_size.is_some() {
+        fs::create_dir_all(&opt.dest_path).map_err(|e| e.to_string())?;
+    }
+
+    let caption_ext = caption_extension(Path::new(&opt.source_path));
+    let mut shard_count = 1usize;
+    let mut in_shard = 0usize;
+    let mut tar = open_tar_shard(&shard_path(0))?;
+
+    let mut exported = 0usize;
+    let mut skipped = 0usize;
+    let mut failures = Vec::new();
+    let source_root = PathBuf::from(&opt.source_path);
+    let source_root = source_root.canonicalize().unwrap_or(source_root);
+
+    for img in images {
         let data = match fs::read(img) {
             Ok(d) => d,
-            Err(_) => {
+            Err(e) => {
                 skipped += 1;
+                failures.push(SkippedImage::new(img, &source_root, format!("Read failed: {}", e)));
                 continue;
             }
         };
-        zip.start_file(&name, opts).map_err(|e| e.to_string())?;
-        zip.write_all(&data).map_err(|e| e.to_string())?;
-
-        let base = name.rsplit_once('.').map(|(n, _)| n).unwrap_or(&name);
-        let txt_name = format!("{}.txt", base);
-        let cap_src = caption_path(img);
-        if cap_src.exists() {
-            if let Ok(content) = fs::read_to_string(&cap_src) {
-                let out = apply_trigger(&content, opt.trigger_word.as_ref());
-                zip.start_file(&txt_name, opts).map_err(|e| e.to_string())?;
-                zip.write_all(out.as_bytes()).map_err(|e| e.to_string())?;
+
+        if let Some(max) = shard_size {
+            if in_shard >= max {
+                tar.finish().map_err(|e| e.to_string())?;
+                tar = open_tar_shard(&shard_path(shard_count))?;
+                shard_count += 1;
+                in_shard = 0;
             }
         }
+
+        // WebDataset groups files by everything before the first dot, so keys are dot-free and
+        // unique across shards regardless of the source names.
+        let key = format!("{:07}", exported);
+        let ext = img
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_else(|| "jpg".to_string());
+        append_tar_entry(&mut tar, &format!("{}.{}", key, ext), &data)?;
+
+        if let Some(out) = export_caption(img, &caption_ext, opt) {
+            let txt_name = format!("{}.{}", key, caption_ext);
+            append_tar_entry(&mut tar, &txt_name, out.as_bytes())?;
+        }
+        in_shard += 1;
         exported += 1;
     }
 
-    zip.finish().map_err(|e| e.to_string())?;
+    tar.finish().map_err(|e| e.to_string())?;
 
     Ok(ExportResult {
         success: true,
         exported_count: exported,
         skipped_count: skipped,
+        skipped_existing_count: 0,
+        skip_reasons: None,
         error: None,
         output_path: opt.dest_path.clone(),
+        shard_count: Some(shard_count),
+        concept_counts: None,
+        skipped: failures,
     })
 }
 
@@ -220,6 +1192,20 @@ pub struct ExportByRatingOptions {
     pub trigger_word: Option<String>,
     #[serde(default)]
     pub sequential_naming: bool,
+    /// If set, only export images with at least this many stars.
+    #[serde(default)]
+    pub min_stars: Option<u8>,
+    /// Include images in subfolders of source_path (default).
+    #[serde(default = "default_true")]
+    pub recursive: bool,
+    /// Folder-name patterns to skip (see ScanIgnore); None skips hidden, originals and
+    /// caption_backups folders. `.lora-studio` is always skipped.
+    #[serde(default)]
+    pub ignore_patterns: Option<Vec<String>>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 fn rating_key(r: ImageRating) -> Option<&'static str> {
@@ -271,18 +1257,32 @@ fn get_rating_for_path(
     "none".to_string()
 }
 
+/// Look up the star rating for a relative path: exact key first, then normalized comparison.
+fn get_stars_for_path(ratings: &RatingsData, rel_key: &str, rel: &str) -> Option<u8> {
+    if let Some(v) = ratings.stars.get(rel_key).or_else(|| ratings.stars.get(rel)) {
+        return Some(*v);
+    }
+    let want = normalize_key_for_lookup(rel_key);
+    ratings
+        .stars
+        .iter()
+        .find(|(k, _)| normalize_key_for_lookup(k) == want)
+        .map(|(_, v)| *v)
+}
+
 #[tauri::command]
-pub async fn export_by_rating(options: ExportByRatingOptions) -> Result<ExportResult, String> {
+pub async fn export_by_rating(options: ExportByRatingOptions) -> Result<ExportResult, CommandError> {
     let root = PathBuf::from(&options.source_path);
     if !root.is_dir() {
-        return Err("Source folder does not exist".to_string());
+        return Err(CommandError::not_found("Source folder does not exist"));
     }
 
-    let canonical = root.canonicalize().map_err(|e| e.to_string())?;
+    let canonical = root.canonicalize()?;
     let project_root = canonical.to_str().unwrap_or(options.source_path.as_str());
     let ratings = load_ratings(project_root);
+    let caption_ext = caption_extension(&canonical);
 
-    let mut by_rating: std::collections::HashMap<&'static str, Vec<PathBuf>> = [
+    let mut by_rating: HashMap<&'static str, Vec<PathBuf>> = [
         ("good", Vec::new()),
         ("bad", Vec::new()),
         ("needs_edit", Vec::new()),
@@ -291,7 +1291,15 @@ pub async fn export_by_rating(options: ExportByRatingOptions) -> Result<ExportRe
     .collect();
 
     // Walk from canonical so strip_prefix(canonical) always succeeds and matches how project stores relative_path.
-    for entry in WalkDir::new(&canonical).follow_links(false).into_iter().filter_map(Result::ok) {
+    let max_depth = if options.recursive { usize::MAX } else { 1 };
+    let ignore = ScanIgnore::new(options.ignore_patterns.as_deref());
+    for entry in WalkDir::new(&canonical)
+        .max_depth(max_depth)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
         let p = entry.path();
         if !p.is_file() || !is_image(p) {
             continue;
@@ -308,6 +1316,13 @@ pub async fn export_by_rating(options: ExportByRatingOptions) -> Result<ExportRe
             continue;
         }
 
+        if let Some(min_stars) = options.min_stars.filter(|&m| m > 0) {
+            let stars = get_stars_for_path(&ratings, &rel_key, &rel).unwrap_or(0);
+            if stars < min_stars {
+                continue;
+            }
+        }
+
         let rating_str = get_rating_for_path(&ratings, &rel_key, &rel, project_root);
         let rating = ImageRating::from_str(&rating_str);
         if let Some(key) = rating_key(rating) {
@@ -316,15 +1331,16 @@ pub async fn export_by_rating(options: ExportByRatingOptions) -> Result<ExportRe
     }
 
     let dest = PathBuf::from(&options.dest_path);
-    fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dest)?;
 
     let mut total_exported = 0usize;
     let mut total_skipped = 0usize;
+    let mut failures = Vec::new();
 
     for (subdir, list) in by_rating.iter_mut() {
         list.sort();
         let sub = dest.join(*subdir);
-        fs::create_dir_all(&sub).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&sub)?;
 
         for (i, img) in list.iter().enumerate() {
             let ext = img.extension().and_then(|e| e.to_str()).unwrap_or("png");
@@ -335,16 +1351,17 @@ pub async fn export_by_rating(options: ExportByRatingOptions) -> Result<ExportRe
             };
 
             let dest_img = sub.join(&name);
-            if fs::copy(img, &dest_img).is_err() {
+            if let Err(e) = fs::copy(img, &dest_img) {
                 total_skipped += 1;
+                failures.push(SkippedImage::new(img, &canonical, format!("Copy failed: {}", e)));
                 continue;
             }
 
             let base = name.rsplit_once('.').map(|(n, _)| n).unwrap_or(&name);
-            let dest_txt = sub.join(format!("{}.txt", base));
-            let cap_src = caption_path(img);
+            let dest_txt = sub.join(format!("{}.{}", base, caption_ext));
+            let cap_src = caption_path(img, &caption_ext);
             if cap_src.exists() {
-                if let Ok(content) = fs::read_to_string(&cap_src) {
+                if let Ok(content) = read_caption_text(&cap_src) {
                     let out = apply_trigger(&content, options.trigger_word.as_ref());
                     let _ = fs::write(&dest_txt, out);
                 }
@@ -357,7 +1374,137 @@ pub async fn export_by_rating(options: ExportByRatingOptions) -> Result<ExportRe
         success: true,
         exported_count: total_exported,
         skipped_count: total_skipped,
+        skipped_existing_count: 0,
+        skip_reasons: None,
         error: None,
         output_path: options.dest_path.clone(),
+        shard_count: None,
+        concept_counts: None,
+        skipped: failures,
+    })
+}
+
+// ============ Ratings report (CSV or JSON) ============
+
+#[derive(Debug, Deserialize)]
+pub struct RatingsReportOptions {
+    pub root_path: String,
+    /// Output file; a .json extension writes JSON, anything else writes CSV.
+    pub dest_path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RatingsReportRow {
+    relative_path: String,
+    rating: &'static str,
+    has_caption: bool,
+    tag_count: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RatingsReportSummary {
+    pub total: usize,
+    pub good: usize,
+    pub bad: usize,
+    pub needs_edit: usize,
+    pub none: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RatingsReportResult {
+    pub output_path: String,
+    pub summary: RatingsReportSummary,
+}
+
+/// Write relative_path,rating,has_caption,tag_count for every image in the project
+/// (unrated images as "none"), followed by per-rating counts.
+#[tauri::command]
+pub fn export_ratings_report(options: RatingsReportOptions) -> Result<RatingsReportResult, CommandError> {
+    let root = PathBuf::from(&options.root_path);
+    if !root.is_dir() {
+        return Err(CommandError::not_found("Project folder does not exist"));
+    }
+    let canonical = root.canonicalize()?;
+    let project_root = canonical.to_str().unwrap_or(options.root_path.as_str());
+    let ratings = load_ratings(project_root);
+    let caption_ext = caption_extension(&canonical);
+
+    let mut rows = Vec::new();
+    let mut summary = RatingsReportSummary::default();
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(Result::ok)
+    {
+        let p = entry.path();
+        if !p.is_file() || !is_image(p) {
+            continue;
+        }
+        let rel = match p.strip_prefix(&canonical) {
+            Ok(r) => r.to_str().map(|s| s.replace('\\', "/")).unwrap_or_default(),
+            Err(_) => continue,
+        };
+        let rel_key = normalize_rel(&rel);
+        if rel_key.is_empty() {
+            continue;
+        }
+
+        let rating = ImageRating::from_str(&get_rating_for_path(&ratings, &rel_key, &rel, project_root));
+        match rating {
+            ImageRating::Good => summary.good += 1,
+            ImageRating::Bad => summary.bad += 1,
+            ImageRating::NeedsEdit => summary.needs_edit += 1,
+            ImageRating::None => summary.none += 1,
+        }
+        summary.total += 1;
+
+        let caption = read_caption_text(caption_path(p, &caption_ext)).ok();
+        let tag_count = caption
+            .as_deref()
+            .map(|c| c.split(',').filter(|t| !t.trim().is_empty()).count())
+            .unwrap_or(0);
+        rows.push(RatingsReportRow {
+            relative_path: rel_key,
+            rating: rating.as_str(),
+            has_caption: caption.is_some(),
+            tag_count,
+        });
+    }
+    rows.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let dest = PathBuf::from(&options.dest_path);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let is_json = dest
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let content = if is_json {
+        let report = serde_json::json!({ "images": rows, "summary": summary });
+        serde_json::to_string_pretty(&report)?
+    } else {
+        let mut csv = String::from("relative_path,rating,has_caption,tag_count\n");
+        for row in &rows {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&row.relative_path),
+                row.rating,
+                row.has_caption,
+                row.tag_count
+            ));
+        }
+        csv.push_str(&format!(
+            "# total={} good={} bad={} needs_edit={} none={}\n",
+            summary.total, summary.good, summary.bad, summary.needs_edit, summary.none
+        ));
+        csv
+    };
+    fs::write(&dest, content)?;
+
+    Ok(RatingsReportResult {
+        output_path: options.dest_path,
+        summary,
     })
 }
@@ -1,15 +1,37 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use once_cell::sync::Lazy;
 use image::imageops::FilterType;
-use image::ImageFormat;
+use image::metadata::Orientation;
+use image::{ImageDecoder, ImageFormat, ImageReader};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
 use std::io::{Cursor, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::Emitter;
+
+use super::captions::read_caption_text;
+use super::crop_status::{load_crop_statuses, save_crop_statuses, CropStatus};
+use super::detect::{detect_faces_in, FaceRegion};
+use super::error::{CommandError, ErrorCode};
+use super::project_config::{caption_extension_for_image, ProjectLookup};
+use super::ratings::{load_ratings, save_ratings};
 
 const THUMB_SIZE: u32 = 256;
 const CACHE_DIR_NAME: &str = "lora-dataset-studio-thumbnails";
+const DEFAULT_CACHE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+const MIN_CACHE_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Max total size of the thumbnail cache; least-recently-used files are evicted above it.
+static CACHE_MAX_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_CACHE_MAX_BYTES);
+
+/// Running total of bytes in the cache dir. None until the first write scans the folder.
+static CACHE_SIZE: Lazy<Mutex<Option<u64>>> = Lazy::new(|| Mutex::new(None));
 
 /// Cache dir under temp. Creates on first use.
 fn thumbnail_cache_dir() -> Result<PathBuf, String> {
@@ -20,8 +42,98 @@ fn thumbnail_cache_dir() -> Result<PathBuf, String> {
     Ok(dir)
 }
 
-/// Cache key from path and mtime so cache invalidates when file changes.
-fn thumbnail_cache_key(path: &std::path::Path, size: u32) -> Result<String, String> {
+/// Cache files with their size and mtime. The mtime doubles as last-access time (see touch_cache_file).
+fn cache_files(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            if !meta.is_file() {
+                return None;
+            }
+            let accessed = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((entry.path(), meta.len(), accessed))
+        })
+        .collect()
+}
+
+fn cache_size(dir: &Path) -> u64 {
+    cache_files(dir).iter().map(|(_, len, _)| len).sum()
+}
+
+/// Mark a cached thumbnail as recently used by bumping its mtime.
+fn touch_cache_file(path: &Path) {
+    if let Ok(f) = fs::File::options().write(true).open(path) {
+        let _ = f.set_modified(SystemTime::now());
+    }
+}
+
+/// Delete least-recently-used cache files until the cache is at most `max_bytes`.
+/// Returns the remaining cache size.
+fn evict_lru(dir: &Path, max_bytes: u64) -> u64 {
+    let mut files = cache_files(dir);
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(_, _, accessed)| *accessed);
+    for (path, len, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(len);
+        }
+    }
+    total
+}
+
+/// Write a thumbnail to the cache and evict LRU files once the cache exceeds its limit.
+/// Evicts down to 90% of the limit so a full cache doesn't rescan the folder on every write.
+fn store_cached_thumbnail(dir: &Path, cache_path: &Path, buf: &[u8]) {
+    let written = fs::File::create(cache_path)
+        .and_then(|mut f| f.write_all(buf))
+        .is_ok();
+    if !written {
+        return;
+    }
+    let max = CACHE_MAX_BYTES.load(Ordering::Relaxed);
+    let mut size = CACHE_SIZE.lock().unwrap();
+    let total = match *size {
+        Some(total) => total + buf.len() as u64,
+        None => cache_size(dir),
+    };
+    *size = Some(if total > max {
+        evict_lru(dir, max / 10 * 9)
+    } else {
+        total
+    });
+}
+
+/// EXIF orientation from the file header. Files without EXIF (or unreadable ones) report NoTransforms.
+fn read_orientation(path: &Path) -> Orientation {
+    ImageReader::open(path)
+        .ok()
+        .and_then(|r| r.with_guessed_format().ok())
+        .and_then(|r| r.into_decoder().ok())
+        .and_then(|mut d| d.orientation().ok())
+        .unwrap_or(Orientation::NoTransforms)
+}
+
+/// Open an image with its EXIF orientation applied, so pixels (and crop coordinates) match what
+/// the user sees in the preview.
+pub(super) fn open_oriented(path: &Path) -> Result<image::DynamicImage, CommandError> {
+    let mut decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = image::DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// Cache key from path, mtime, EXIF orientation and encoding so cache invalidates when the file
+/// changes, thumbnails cached before orientation was applied are not reused, and switching
+/// format or quality never serves a stale entry.
+fn thumbnail_cache_key(path: &Path, size: u32, encoding: ThumbnailEncoding) -> Result<String, String> {
     let meta = fs::metadata(path).map_err(|e| e.to_string())?;
     let mtime = meta
         .modified()
@@ -35,10 +147,126 @@ fn thumbnail_cache_key(path: &std::path::Path, size: u32) -> Result<String, Stri
     hasher.update(path_str.as_bytes());
     hasher.update(mtime.as_bytes());
     hasher.update(size.to_le_bytes());
+    hasher.update([read_orientation(path).to_exif()]);
+    // JPEG at the default quality hashes as before, so existing cache entries stay valid.
+    if encoding != ThumbnailEncoding::default() {
+        hasher.update(encoding.format.extension().as_bytes());
+        hasher.update([encoding.quality]);
+    }
     let hash = hasher.finalize();
     Ok(hex::encode(&hash[..16]))
 }
 
+/// Thumbnail encoding. Both formats are lossy and honor the quality setting; WebP (via libwebp)
+/// keeps alpha.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThumbnailFormat {
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    fn mime(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+/// JPEG quality the image crate used for thumbnails before it was configurable.
+const DEFAULT_THUMB_QUALITY: u8 = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ThumbnailEncoding {
+    format: ThumbnailFormat,
+    quality: u8,
+}
+
+impl Default for ThumbnailEncoding {
+    fn default() -> Self {
+        Self {
+            format: ThumbnailFormat::Jpeg,
+            quality: DEFAULT_THUMB_QUALITY,
+        }
+    }
+}
+
+impl ThumbnailEncoding {
+    fn new(format: ThumbnailFormat, quality: Option<u8>) -> Self {
+        Self {
+            format,
+            quality: quality.unwrap_or(DEFAULT_THUMB_QUALITY).clamp(1, 100),
+        }
+    }
+
+    fn cache_path(self, cache_dir: &Path, key: &str) -> PathBuf {
+        cache_dir.join(format!("{}.{}", key, self.format.extension()))
+    }
+
+    fn encode(self, thumb: &image::DynamicImage) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::new();
+        match self.format {
+            ThumbnailFormat::Jpeg => {
+                let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                    &mut buf,
+                    self.quality,
+                );
+                image::DynamicImage::ImageRgb8(thumb.to_rgb8())
+                    .write_with_encoder(encoder)
+                    .map_err(|e| e.to_string())?;
+            }
+            ThumbnailFormat::Webp => {
+                let (w, h) = (thumb.width(), thumb.height());
+                let quality = self.quality as f32;
+                let encoded = if thumb.color().has_alpha() {
+                    webp::Encoder::from_rgba(&thumb.to_rgba8(), w, h).encode(quality)
+                } else {
+                    webp::Encoder::from_rgb(&thumb.to_rgb8(), w, h).encode(quality)
+                };
+                buf.extend_from_slice(&encoded);
+            }
+        }
+        Ok(buf)
+    }
+
+    fn data_url(self, buf: &[u8]) -> String {
+        format!("data:{};base64,{}", self.format.mime(), BASE64.encode(buf))
+    }
+}
+
+/// Whether our encoder for `format` stores an alpha channel (WebP is always written lossless).
+fn format_keeps_alpha(format: ImageFormat) -> bool {
+    matches!(format, ImageFormat::Png | ImageFormat::WebP | ImageFormat::Gif)
+}
+
+/// Crop a region, keeping RGBA when the source has alpha and the output format can store it.
+/// Falls back to RGB8 otherwise (e.g. JPEG).
+fn crop_region(
+    img: &image::DynamicImage,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+) -> image::DynamicImage {
+    let cropped = img.crop_imm(x, y, width, height);
+    if img.color().has_alpha() && format_keeps_alpha(format) {
+        image::DynamicImage::from(cropped.to_rgba8())
+    } else {
+        image::DynamicImage::from(cropped.to_rgb8())
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CropImagePayload {
     pub image_path: String,
@@ -50,14 +278,373 @@ pub struct CropImagePayload {
     pub flip_x: bool,
     #[serde(default)]
     pub flip_y: bool,
+    /// Clockwise rotation in degrees. Multiples of 90 are lossless; other angles are interpolated.
+    #[serde(default)]
+    pub rotate_degrees: f32,
+    /// Color for the corners exposed by a non-90° rotation. Unset leaves them transparent
+    /// (black for formats without alpha).
+    #[serde(default)]
+    pub rotate_fill: Option<[u8; 3]>,
+    /// After a non-90° rotation, crop to the largest upright rectangle with no exposed corners.
     #[serde(default)]
-    pub rotate_degrees: i32,
+    pub rotate_auto_crop: bool,
     /// If true, save cropped image to a new file (keeps original). Returns new path.
     #[serde(default)]
     pub save_as_new: bool,
     /// If set, resize output to this size (square) for LoRA/training (e.g. 512 or 1024).
     #[serde(default)]
     pub output_size: Option<u32>,
+    /// Explicit output dimensions (e.g. 832x1216). Take precedence over output_size; see resize_output.
+    #[serde(default)]
+    pub output_width: Option<u32>,
+    #[serde(default)]
+    pub output_height: Option<u32>,
+    /// Before overwriting in place, keep the untouched source in `.lora-studio/originals/` (see restore_original).
+    #[serde(default)]
+    pub backup_original: bool,
+    /// Composite transparent images onto this RGB color (output has no alpha). Unset keeps current behavior.
+    #[serde(default)]
+    pub flatten_background: Option<[u8; 3]>,
+    /// Project root, used to resolve relative paths in `.lora-studio` metadata. When set, the
+    /// cropped file (in place or new) gets crop status "cropped".
+    #[serde(default)]
+    pub root_path: Option<String>,
+    /// With save_as_new and root_path, give the new file the source's rating and stars.
+    #[serde(default)]
+    pub copy_metadata: bool,
+    /// With save_as_new and root_path, mark the source image's crop status as "has_crop".
+    #[serde(default)]
+    pub mark_source_has_crop: bool,
+}
+
+/// Path relative to the project root with forward slashes, as used for metadata keys.
+fn project_relative(root: &Path, path: &Path) -> Option<String> {
+    let root = root.canonicalize().ok()?;
+    let path = path.canonicalize().ok()?;
+    let rel = path.strip_prefix(&root).ok()?;
+    Some(rel.to_str()?.replace('\\', "/"))
+}
+
+/// Set the crop status of images in the project (skipping paths outside the root).
+fn set_crop_statuses(root_path: &str, updates: &[(&Path, CropStatus)]) -> Result<(), String> {
+    let root = Path::new(root_path);
+    let mut crop_statuses = load_crop_statuses(root_path)?;
+    for (path, status) in updates {
+        if let Some(rel) = project_relative(root, path) {
+            crop_statuses.statuses.insert(rel, status.as_str().to_string());
+        }
+    }
+    save_crop_statuses(root_path, &crop_statuses)
+}
+
+/// Copy the source image's rating, stars and crop status to derived images.
+fn copy_image_metadata(root_path: &str, source: &Path, outputs: &[PathBuf]) -> Result<(), String> {
+    let root = Path::new(root_path);
+    let Some(source_rel) = project_relative(root, source) else {
+        return Ok(());
+    };
+    let output_rels: Vec<String> = outputs
+        .iter()
+        .filter_map(|out| project_relative(root, out))
+        .collect();
+
+    let mut ratings = load_ratings(root_path);
+    let rating = ratings.ratings.get(&source_rel).cloned();
+    let stars = ratings.stars.get(&source_rel).copied();
+    if rating.is_some() || stars.is_some() {
+        for rel in &output_rels {
+            if let Some(rating) = &rating {
+                ratings.ratings.insert(rel.clone(), rating.clone());
+            }
+            if let Some(stars) = stars {
+                ratings.stars.insert(rel.clone(), stars);
+            }
+        }
+        save_ratings(root_path, &ratings)?;
+    }
+
+    let mut crop_statuses = load_crop_statuses(root_path)?;
+    if let Some(status) = crop_statuses.statuses.get(&source_rel).cloned() {
+        for rel in output_rels {
+            crop_statuses.statuses.insert(rel, status.clone());
+        }
+        save_crop_statuses(root_path, &crop_statuses)?;
+    }
+    Ok(())
+}
+
+/// Composite an image with alpha onto a solid background color. Opaque images are returned as-is.
+fn flatten_alpha(img: image::DynamicImage, background: [u8; 3]) -> image::DynamicImage {
+    composite_over(img, |_, _| background)
+}
+
+/// Composite an image with alpha over a per-pixel background color; images without alpha pass through.
+fn composite_over(
+    img: image::DynamicImage,
+    background_at: impl Fn(u32, u32) -> [u8; 3],
+) -> image::DynamicImage {
+    if !img.color().has_alpha() {
+        return img;
+    }
+    let rgba = img.to_rgba8();
+    let flat = image::RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let background = background_at(x, y);
+        let a = a as u32;
+        let blend = |c: u8, bg: u8| ((c as u32 * a + bg as u32 * (255 - a) + 127) / 255) as u8;
+        image::Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    });
+    image::DynamicImage::ImageRgb8(flat)
+}
+
+/// Fraction of pixels clipped at each end of the histogram by auto levels/contrast.
+const LEVELS_CLIP_FRACTION: f64 = 0.005;
+
+/// Low and high histogram values after clipping LEVELS_CLIP_FRACTION of the pixels at each end.
+fn histogram_bounds(hist: &[u64; 256], total: u64) -> (u8, u8) {
+    let clip = (total as f64 * LEVELS_CLIP_FRACTION) as u64;
+    let mut seen = 0u64;
+    let low = hist
+        .iter()
+        .position(|&n| {
+            seen += n;
+            seen > clip
+        })
+        .unwrap_or(0);
+    seen = 0;
+    let high = 255
+        - hist
+            .iter()
+            .rev()
+            .position(|&n| {
+                seen += n;
+                seen > clip
+            })
+            .unwrap_or(0);
+    (low as u8, high as u8)
+}
+
+/// Lookup table mapping [low, high] linearly onto [0, 255].
+fn stretch_table(low: u8, high: u8) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    for (v, out) in table.iter_mut().enumerate() {
+        *out = if high <= low {
+            v as u8
+        } else {
+            ((v as f32 - low as f32) * 255.0 / (high - low) as f32)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+    }
+    table
+}
+
+/// Stretch the histogram to the full 0-255 range. `per_channel` (auto levels) stretches R, G and
+/// B independently, which also removes color casts; otherwise (auto contrast) one stretch from
+/// the luminance histogram is applied to all channels so hues are kept. Alpha is untouched.
+fn stretch_levels(img: image::DynamicImage, per_channel: bool) -> image::DynamicImage {
+    let has_alpha = img.color().has_alpha();
+    let mut rgba = img.to_rgba8();
+    let mut hists = [[0u64; 256]; 4];
+    for p in rgba.pixels() {
+        let [r, g, b, _] = p.0;
+        hists[0][r as usize] += 1;
+        hists[1][g as usize] += 1;
+        hists[2][b as usize] += 1;
+        let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round() as usize;
+        hists[3][luma.min(255)] += 1;
+    }
+    let total = rgba.width() as u64 * rgba.height() as u64;
+    let tables: [[u8; 256]; 3] = if per_channel {
+        std::array::from_fn(|c| {
+            let (low, high) = histogram_bounds(&hists[c], total);
+            stretch_table(low, high)
+        })
+    } else {
+        let (low, high) = histogram_bounds(&hists[3], total);
+        [stretch_table(low, high); 3]
+    };
+    for p in rgba.pixels_mut() {
+        for c in 0..3 {
+            p.0[c] = tables[c][p.0[c] as usize];
+        }
+    }
+    if has_alpha {
+        image::DynamicImage::ImageRgba8(rgba)
+    } else {
+        image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(rgba).to_rgb8())
+    }
+}
+
+/// Where the original of an in-place cropped image is kept: `.lora-studio/originals/<relative path>`
+/// under the nearest ancestor that has a `.lora-studio` folder (the image's own folder if none).
+fn original_backup_path(image_path: &Path) -> Option<PathBuf> {
+    let parent = image_path.parent()?;
+    let root = image_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(".lora-studio").is_dir())
+        .unwrap_or(parent);
+    let rel = image_path.strip_prefix(root).ok()?;
+    Some(root.join(".lora-studio").join("originals").join(rel))
+}
+
+/// Copy the source to its originals slot unless a backup already exists, so repeated
+/// crops keep the very first version.
+fn backup_original(image_path: &Path) -> Result<(), String> {
+    let backup = original_backup_path(image_path).ok_or("Cannot back up image outside a folder")?;
+    if backup.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = backup.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::copy(image_path, &backup)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to back up original: {}", e))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreOriginalPayload {
+    pub image_path: String,
+}
+
+/// Put back the original saved by crop_image's backup_original. The backup is kept so the
+/// image can be re-cropped and restored again.
+#[tauri::command]
+pub fn restore_original(payload: RestoreOriginalPayload) -> Result<(), CommandError> {
+    let path = PathBuf::from(&payload.image_path);
+    let backup = original_backup_path(&path)
+        .filter(|b| b.is_file())
+        .ok_or("No original backup for this image")?;
+    fs::copy(&backup, &path)?;
+    Ok(())
+}
+
+/// Rotate clockwise by `degrees`. Multiples of 90 use the lossless rotate90/180/270; other angles
+/// are bilinearly resampled onto the rotated bounding box, with exposed corners set to `fill`
+/// (transparent when unset), or cropped to the largest inscribed rectangle when `auto_crop` is set.
+fn rotate_image(
+    img: image::DynamicImage,
+    degrees: f32,
+    fill: Option<[u8; 3]>,
+    auto_crop: bool,
+    format: ImageFormat,
+) -> image::DynamicImage {
+    let degrees = degrees.rem_euclid(360.0);
+    let quarter_turns = degrees / 90.0;
+    if (quarter_turns - quarter_turns.round()).abs() < 1e-3 {
+        return match quarter_turns.round() as u32 % 4 {
+            1 => img.rotate90(),
+            2 => img.rotate180(),
+            3 => img.rotate270(),
+            _ => img,
+        };
+    }
+
+    // Transparent corners need alpha only while they are kept (no fill color, no auto-crop).
+    let keep_alpha = format_keeps_alpha(format)
+        && (img.color().has_alpha() || (fill.is_none() && !auto_crop));
+    let src = img.to_rgba8();
+    let (w, h) = (src.width() as f64, src.height() as f64);
+    let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+    let out_w = (w * cos.abs() + h * sin.abs()).round().max(1.0) as u32;
+    let out_h = (w * sin.abs() + h * cos.abs()).round().max(1.0) as u32;
+    let background = match fill {
+        Some([r, g, b]) => image::Rgba([r, g, b, 255]),
+        None => image::Rgba([0, 0, 0, 0]),
+    };
+
+    let sample = |x: i64, y: i64| -> [f64; 4] {
+        let px = if x < 0 || y < 0 || x >= src.width() as i64 || y >= src.height() as i64 {
+            background
+        } else {
+            *src.get_pixel(x as u32, y as u32)
+        };
+        px.0.map(|c| c as f64)
+    };
+    let (ocx, ocy) = (out_w as f64 / 2.0, out_h as f64 / 2.0);
+    let rotated = image::RgbaImage::from_fn(out_w, out_h, |x, y| {
+        // Inverse-map the output pixel center into source coordinates.
+        let (dx, dy) = (x as f64 + 0.5 - ocx, y as f64 + 0.5 - ocy);
+        let sx = dx * cos + dy * sin + w / 2.0 - 0.5;
+        let sy = -dx * sin + dy * cos + h / 2.0 - 0.5;
+        let (x0, y0) = (sx.floor(), sy.floor());
+        let (fx, fy) = (sx - x0, sy - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let (p00, p10) = (sample(x0, y0), sample(x0 + 1, y0));
+        let (p01, p11) = (sample(x0, y0 + 1), sample(x0 + 1, y0 + 1));
+        image::Rgba(std::array::from_fn(|i| {
+            let top = p00[i] + (p10[i] - p00[i]) * fx;
+            let bottom = p01[i] + (p11[i] - p01[i]) * fx;
+            (top + (bottom - top) * fy).round().clamp(0.0, 255.0) as u8
+        }))
+    });
+
+    let mut out = image::DynamicImage::ImageRgba8(rotated);
+    if auto_crop {
+        let (cw, ch) = inscribed_rect_size(w, h, sin.abs(), cos.abs());
+        let cw = (cw.floor() as u32).clamp(1, out_w);
+        let ch = (ch.floor() as u32).clamp(1, out_h);
+        out = out.crop_imm((out_w - cw) / 2, (out_h - ch) / 2, cw, ch);
+    }
+    if keep_alpha {
+        out
+    } else {
+        image::DynamicImage::ImageRgb8(out.to_rgb8())
+    }
+}
+
+/// Size of the largest axis-aligned rectangle inside a w×h rectangle rotated by an angle with
+/// the given |sin| and |cos|.
+fn inscribed_rect_size(w: f64, h: f64, sin: f64, cos: f64) -> (f64, f64) {
+    let (long, short) = if w >= h { (w, h) } else { (h, w) };
+    if short <= 2.0 * sin * cos * long || (sin - cos).abs() < 1e-10 {
+        // Half-constrained: two corners of the inscribed rectangle touch the longer sides.
+        let x = 0.5 * short;
+        if w >= h {
+            (x / sin, x / cos)
+        } else {
+            (x / cos, x / sin)
+        }
+    } else {
+        let cos_2a = cos * cos - sin * sin;
+        ((w * cos - h * sin) / cos_2a, (h * cos - w * sin) / cos_2a)
+    }
+}
+
+fn valid_output_side(side: Option<u32>) -> Option<u32> {
+    side.filter(|s| (64..=2048).contains(s))
+}
+
+/// Resize a crop for output: both dimensions set resizes to exactly that size; one dimension
+/// derives the other from the crop's aspect ratio; otherwise falls back to the square output_size.
+fn resize_output(
+    img: image::DynamicImage,
+    output_size: Option<u32>,
+    output_width: Option<u32>,
+    output_height: Option<u32>,
+) -> image::DynamicImage {
+    let (w, h) = (img.width().max(1) as f64, img.height().max(1) as f64);
+    match (valid_output_side(output_width), valid_output_side(output_height)) {
+        (Some(ow), Some(oh)) => img.resize_exact(ow, oh, FilterType::Triangle),
+        (Some(ow), None) => {
+            let oh = ((ow as f64 * h / w).round() as u32).max(1);
+            img.resize_exact(ow, oh, FilterType::Triangle)
+        }
+        (None, Some(oh)) => {
+            let ow = ((oh as f64 * w / h).round() as u32).max(1);
+            img.resize_exact(ow, oh, FilterType::Triangle)
+        }
+        (None, None) => match valid_output_side(output_size) {
+            Some(sz) => img.resize(sz, sz, FilterType::Triangle),
+            None => img,
+        },
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,6 +652,144 @@ pub struct GetThumbnailPayload {
     pub path: String,
     #[serde(default)]
     pub size: Option<u32>,
+    /// Thumbnail encoding (default JPEG).
+    #[serde(default)]
+    pub format: ThumbnailFormat,
+    /// JPEG/WebP quality 1-100 (default 75).
+    #[serde(default)]
+    pub quality: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetThumbnailCacheLimitPayload {
+    pub max_bytes: u64,
+}
+
+/// Set the max thumbnail cache size (min 16 MB) and evict immediately if the cache is over it.
+#[tauri::command]
+pub fn set_thumbnail_cache_limit(payload: SetThumbnailCacheLimitPayload) -> Result<(), CommandError> {
+    let max = payload.max_bytes.max(MIN_CACHE_MAX_BYTES);
+    CACHE_MAX_BYTES.store(max, Ordering::Relaxed);
+    let dir = thumbnail_cache_dir()?;
+    let mut size = CACHE_SIZE.lock().unwrap();
+    *size = Some(evict_lru(&dir, max));
+    Ok(())
+}
+
+/// Delete every cached thumbnail. Returns the number of bytes freed.
+#[tauri::command]
+pub fn clear_thumbnail_cache() -> Result<u64, CommandError> {
+    let dir = thumbnail_cache_dir()?;
+    let mut size = CACHE_SIZE.lock().unwrap();
+    let mut freed = 0u64;
+    for (path, len, _) in cache_files(&dir) {
+        if fs::remove_file(&path).is_ok() {
+            freed += len;
+        }
+    }
+    *size = Some(cache_size(&dir));
+    Ok(freed)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThumbnailCacheStats {
+    pub file_count: usize,
+    pub total_bytes: u64,
+    /// Current cache limit (see set_thumbnail_cache_limit).
+    pub max_bytes: u64,
+}
+
+/// Number and total size of cached thumbnails.
+#[tauri::command]
+pub fn thumbnail_cache_stats() -> Result<ThumbnailCacheStats, CommandError> {
+    let dir = thumbnail_cache_dir()?;
+    let files = cache_files(&dir);
+    let total_bytes = files.iter().map(|(_, len, _)| len).sum();
+    *CACHE_SIZE.lock().unwrap() = Some(total_bytes);
+    Ok(ThumbnailCacheStats {
+        file_count: files.len(),
+        total_bytes,
+        max_bytes: CACHE_MAX_BYTES.load(Ordering::Relaxed),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrewarmThumbnailsPayload {
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub size: Option<u32>,
+    /// Must match the format and quality later passed to get_thumbnail to hit the cache.
+    #[serde(default)]
+    pub format: ThumbnailFormat,
+    #[serde(default)]
+    pub quality: Option<u8>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrewarmThumbnailsResult {
+    pub generated_count: usize,
+    /// Thumbnails that were already cached.
+    pub cached_count: usize,
+    pub failed_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrewarmThumbnailsProgress {
+    pub current: u32,
+    pub total: u32,
+}
+
+/// Generate cached thumbnails (same cache and keys as get_thumbnail) for many images in the
+/// background, emitting thumbnail-prewarm-progress events. Nothing is returned to the webview
+/// but counts, so it is cheap to run right after open_project.
+#[tauri::command]
+pub fn prewarm_thumbnails(
+    payload: PrewarmThumbnailsPayload,
+    window: tauri::Window,
+) -> Result<PrewarmThumbnailsResult, CommandError> {
+    let size = payload.size.unwrap_or(THUMB_SIZE).min(512);
+    let encoding = ThumbnailEncoding::new(payload.format, payload.quality);
+    let cache_dir = thumbnail_cache_dir()?;
+    let total = payload.paths.len() as u32;
+    let completed = AtomicU32::new(0);
+
+    // Some(true) = generated, Some(false) = already cached, None = failed
+    let outcomes: Vec<Option<bool>> = payload
+        .paths
+        .par_iter()
+        .map(|path_str| {
+            let path = PathBuf::from(path_str);
+            let outcome = (|| {
+                if !path.is_file() {
+                    return None;
+                }
+                let key = thumbnail_cache_key(&path, size, encoding).ok()?;
+                let cache_path = encoding.cache_path(&cache_dir, &key);
+                if cache_path.is_file() {
+                    touch_cache_file(&cache_path);
+                    return Some(false);
+                }
+                let thumb = open_oriented(&path).ok()?.resize(size, size, FilterType::Triangle);
+                let buf = encoding.encode(&thumb).ok()?;
+                store_cached_thumbnail(&cache_dir, &cache_path, &buf);
+                Some(true)
+            })();
+            let current = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            if current.is_multiple_of(25) || current == total {
+                let _ = window.emit(
+                    "thumbnail-prewarm-progress",
+                    PrewarmThumbnailsProgress { current, total },
+                );
+            }
+            outcome
+        })
+        .collect();
+
+    Ok(PrewarmThumbnailsResult {
+        generated_count: outcomes.iter().filter(|o| **o == Some(true)).count(),
+        cached_count: outcomes.iter().filter(|o| **o == Some(false)).count(),
+        failed_count: outcomes.iter().filter(|o| o.is_none()).count(),
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,54 +798,91 @@ pub struct GetImageDataUrlPayload {
     /// Max length of the longest side (for preview); 0 = full size.
     #[serde(default)]
     pub max_side: Option<u32>,
+    /// Background for images with alpha: "checker", "white", "black", "transparent" (PNG output
+    /// that keeps alpha) or an RGB triple. Unset keeps the plain JPEG encode.
+    #[serde(default)]
+    pub bg: Option<PreviewBackground>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewBackgroundName {
+    Checker,
+    White,
+    Black,
+    Transparent,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum PreviewBackground {
+    Named(PreviewBackgroundName),
+    Color([u8; 3]),
 }
 
-/// Generates a thumbnail for the image at path. Returns a data URL (base64 JPEG).
-/// Uses an on-disk cache under temp (keyed by path + mtime + size) to avoid regenerating on scroll.
+/// Checkerboard square size in preview pixels.
+const CHECKER_SQUARE: u32 = 8;
+
+fn checker_color(x: u32, y: u32) -> [u8; 3] {
+    if (x / CHECKER_SQUARE + y / CHECKER_SQUARE).is_multiple_of(2) {
+        [255, 255, 255]
+    } else {
+        [204, 204, 204]
+    }
+}
+
+/// Generates a thumbnail for the image at path. Returns a data URL (base64 JPEG or WebP).
+/// Uses an on-disk cache under temp (keyed by path + mtime + size + encoding) to avoid regenerating on scroll.
 #[tauri::command]
-pub fn get_thumbnail(payload: GetThumbnailPayload) -> Result<String, String> {
+pub fn get_thumbnail(payload: GetThumbnailPayload) -> Result<String, CommandError> {
     let path = PathBuf::from(&payload.path);
     if !path.exists() || !path.is_file() {
-        return Err("File not found".to_string());
+        return Err(CommandError::not_found("File not found"));
     }
 
     let size = payload.size.unwrap_or(THUMB_SIZE).min(512);
+    let encoding = ThumbnailEncoding::new(payload.format, payload.quality);
     let cache_dir = thumbnail_cache_dir()?;
-    let key = thumbnail_cache_key(&path, size)?;
-    let cache_path = cache_dir.join(format!("{}.jpg", key));
+    let key = thumbnail_cache_key(&path, size, encoding)?;
+    let cache_path = encoding.cache_path(&cache_dir, &key);
 
     if cache_path.exists() && cache_path.is_file() {
         let mut buf = Vec::new();
-        let mut f = fs::File::open(&cache_path).map_err(|e| e.to_string())?;
-        f.read_to_end(&mut buf).map_err(|e| e.to_string())?;
-        let b64 = BASE64.encode(&buf);
-        return Ok(format!("data:image/jpeg;base64,{b64}"));
+        let mut f = fs::File::open(&cache_path)?;
+        f.read_to_end(&mut buf)?;
+        touch_cache_file(&cache_path);
+        return Ok(encoding.data_url(&buf));
     }
 
-    let img = image::open(&path).map_err(|e| e.to_string())?;
+    let img = open_oriented(&path)?;
     let thumb = img.resize(size, size, FilterType::Triangle);
-    let mut buf = Vec::new();
-    thumb
-        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
-        .map_err(|e| e.to_string())?;
+    let buf = encoding.encode(&thumb)?;
 
-    if let Ok(mut f) = fs::File::create(&cache_path) {
-        let _ = f.write_all(&buf);
-    }
+    store_cached_thumbnail(&cache_dir, &cache_path, &buf);
 
-    let b64 = BASE64.encode(&buf);
-    Ok(format!("data:image/jpeg;base64,{b64}"))
+    Ok(encoding.data_url(&buf))
 }
 
-/// Load image from path and return as data URL (for preview/crop so webview doesn't need asset protocol).
-#[tauri::command]
-pub fn get_image_data_url(payload: GetImageDataUrlPayload) -> Result<String, String> {
+#[derive(Debug, Serialize)]
+pub struct ImagePreview {
+    pub data_url: String,
+    /// Dimensions of the source image (EXIF orientation applied), i.e. the crop coordinate space.
+    pub orig_width: u32,
+    pub orig_height: u32,
+    /// Dimensions of the encoded preview after max_side downscaling.
+    pub shown_width: u32,
+    pub shown_height: u32,
+}
+
+/// Decode, orient and optionally downscale an image for preview.
+fn image_preview(payload: &GetImageDataUrlPayload) -> Result<ImagePreview, CommandError> {
     let path = PathBuf::from(&payload.path);
     if !path.exists() || !path.is_file() {
-        return Err("File not found".to_string());
+        return Err(CommandError::not_found("File not found"));
     }
 
-    let mut img = image::open(&path).map_err(|e| e.to_string())?;
+    let mut img = open_oriented(&path)?;
+    let (orig_width, orig_height) = (img.width(), img.height());
     let max_side = payload.max_side.unwrap_or(0);
     if max_side > 0 {
         let (w, h) = (img.width(), img.height());
@@ -133,23 +895,101 @@ pub fn get_image_data_url(payload: GetImageDataUrlPayload) -> Result<String, Str
         }
     }
 
+    let (format, mime) = match payload.bg {
+        Some(PreviewBackground::Named(PreviewBackgroundName::Transparent))
+            if img.color().has_alpha() =>
+        {
+            (ImageFormat::Png, "image/png")
+        }
+        _ => (ImageFormat::Jpeg, "image/jpeg"),
+    };
+    img = match payload.bg {
+        Some(PreviewBackground::Named(name)) => match name {
+            PreviewBackgroundName::Checker => composite_over(img, checker_color),
+            PreviewBackgroundName::White => flatten_alpha(img, [255, 255, 255]),
+            PreviewBackgroundName::Black => flatten_alpha(img, [0, 0, 0]),
+            PreviewBackgroundName::Transparent => img,
+        },
+        Some(PreviewBackground::Color(color)) => flatten_alpha(img, color),
+        None => img,
+    };
+
     let mut buf = Vec::new();
-    img.write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
-        .map_err(|e| e.to_string())?;
+    img.write_to(&mut Cursor::new(&mut buf), format)?;
     let b64 = BASE64.encode(&buf);
-    Ok(format!("data:image/jpeg;base64,{b64}"))
+    Ok(ImagePreview {
+        data_url: format!("data:{mime};base64,{b64}"),
+        orig_width,
+        orig_height,
+        shown_width: img.width(),
+        shown_height: img.height(),
+    })
+}
+
+/// Load image from path and return as data URL (for preview/crop so webview doesn't need asset protocol).
+#[tauri::command]
+pub fn get_image_data_url(payload: GetImageDataUrlPayload) -> Result<String, CommandError> {
+    image_preview(&payload).map(|preview| preview.data_url)
+}
+
+/// Like get_image_data_url, but also returns the original and shown dimensions so crop
+/// rectangles drawn on a downscaled preview can be mapped back to source pixels exactly.
+#[tauri::command]
+pub fn get_image_preview(payload: GetImageDataUrlPayload) -> Result<ImagePreview, CommandError> {
+    image_preview(&payload)
+}
+
+/// Next free `{stem}_{n}_crop.{ext}` path next to the source image.
+fn unique_crop_path(path: &Path, ext: &str) -> Result<PathBuf, String> {
+    let parent = path.parent().unwrap_or(path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    for n in 1..=9999u32 {
+        let candidate = parent.join(format!("{}_{}_crop.{}", stem, n, ext));
+        if !candidate.exists() {
+            return Ok(candidate);
+        }
+    }
+    Err("Could not create unique filename for new image".to_string())
+}
+
+/// Next free `{stem}_face{n}.{ext}` path next to the source image, trying n from `*next` upward.
+/// `*next` is advanced past the returned number so one image's faces get increasing numbers.
+fn next_face_crop_path(path: &Path, ext: &str, next: &mut u32) -> Result<PathBuf, String> {
+    let parent = path.parent().unwrap_or(path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    while *next <= 9999 {
+        let candidate = parent.join(format!("{}_face{}.{}", stem, *next, ext));
+        *next += 1;
+        if !candidate.exists() {
+            return Ok(candidate);
+        }
+    }
+    Err("Could not create unique filename for new image".to_string())
+}
+
+/// Copy the source image's caption (if any) next to a derived image.
+fn copy_caption(source: &Path, out_path: &Path, caption_ext: &str) {
+    let caption_path = source.with_extension(caption_ext);
+    if caption_path.exists() {
+        if let Ok(content) = read_caption_text(&caption_path) {
+            let _ = fs::write(out_path.with_extension(caption_ext), content.trim());
+        }
+    }
 }
 
 /// Crop (and optionally flip/rotate) an image. Overwrites the file unless save_as_new is true.
 /// Returns Some(new_path) when save_as_new is true, None otherwise.
 #[tauri::command]
-pub fn crop_image(payload: CropImagePayload) -> Result<Option<String>, String> {
+pub fn crop_image(payload: CropImagePayload) -> Result<Option<String>, CommandError> {
     let path = PathBuf::from(&payload.image_path);
     if !path.exists() || !path.is_file() {
-        return Err("Image file not found".to_string());
+        return Err(CommandError::not_found("Image file not found"));
     }
 
-    let img = image::open(&path).map_err(|e| e.to_string())?;
+    let mut img = open_oriented(&path)?;
+    if let Some(bg) = payload.flatten_background {
+        img = flatten_alpha(img, bg);
+    }
 
     let (w, h) = (img.width(), img.height());
     let x = payload.x.min(w.saturating_sub(1));
@@ -157,77 +997,303 @@ pub fn crop_image(payload: CropImagePayload) -> Result<Option<String>, String> {
     let cw = payload.width.min(w.saturating_sub(x));
     let ch = payload.height.min(h.saturating_sub(y));
 
-    if cw == 0 || ch == 0 {
-        return Err("Crop region has zero size".to_string());
-    }
+    if cw == 0 || ch == 0 {
+        return Err(CommandError::invalid_input("Crop region has zero size"));
+    }
+
+    let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
+
+    // Crop first (in original image coordinates), then apply flip/rotate to the cropped result
+    let mut out_img = crop_region(&img, x, y, cw, ch, format);
+
+    if payload.flip_x {
+        out_img = out_img.fliph();
+    }
+    if payload.flip_y {
+        out_img = out_img.flipv();
+    }
+
+    out_img = rotate_image(
+        out_img,
+        payload.rotate_degrees,
+        payload.rotate_fill,
+        payload.rotate_auto_crop,
+        format,
+    );
+
+    // Optional: resize to training size for LoRA
+    out_img = resize_output(
+        out_img,
+        payload.output_size,
+        payload.output_width,
+        payload.output_height,
+    );
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png");
+    let out_path: PathBuf = if payload.save_as_new {
+        unique_crop_path(&path, ext)?
+    } else {
+        if payload.backup_original {
+            backup_original(&path)?;
+        }
+        path.clone()
+    };
+
+    let mut file = std::io::BufWriter::new(
+        std::fs::File::create(&out_path)?,
+    );
+    out_img
+        .write_to(&mut file, format)
+        ?;
+
+    // When saving as new, copy the source caption to the new image so LoRA workflow keeps tags
+    if payload.save_as_new {
+        copy_caption(&path, &out_path, &caption_extension_for_image(&path));
+        if let (true, Some(root)) = (payload.copy_metadata, &payload.root_path) {
+            copy_image_metadata(root, &path, std::slice::from_ref(&out_path))?;
+        }
+    }
+    if let Some(root) = &payload.root_path {
+        let mut updates = vec![(out_path.as_path(), CropStatus::Cropped)];
+        if payload.save_as_new && payload.mark_source_has_crop {
+            updates.push((path.as_path(), CropStatus::HasCrop));
+        }
+        set_crop_statuses(root, &updates)?;
+    }
+
+    Ok(if payload.save_as_new {
+        Some(out_path.to_string_lossy().into_owned())
+    } else {
+        None
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaceCropStrategy {
+    /// One crop around the highest-confidence face (the larger one on a tie).
+    #[default]
+    MostConfident,
+    /// One crop around the largest detected face (the more confident one on a tie).
+    Largest,
+    /// One crop per detected face, saved with _face1, _face2, ... suffixes (numbers already taken
+    /// next to the image are skipped, so earlier crops are never overwritten).
+    All,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutocropToFacePayload {
+    pub image_path: String,
+    /// If set, resize each crop to this size (square), like crop_image's output_size.
+    #[serde(default)]
+    pub output_size: Option<u32>,
+    /// Extra margin around the face on each side, as a fraction of the face size.
+    #[serde(default = "default_face_padding")]
+    pub padding: f32,
+    #[serde(default)]
+    pub strategy: FaceCropStrategy,
+}
+
+fn default_face_padding() -> f32 {
+    0.5
+}
 
-    // Crop first (in original image coordinates), then apply flip/rotate to the cropped result
-    let cropped_sub = img.crop_imm(x, y, cw, ch);
-    let mut out_img = image::DynamicImage::from(cropped_sub.to_rgb8());
+/// Square crop around a face: pad each side by `padding` x face size, grow to a square,
+/// then shift/shrink to stay inside the image.
+fn face_crop_rect(face: &FaceRegion, padding: f32, img_w: u32, img_h: u32) -> (u32, u32, u32, u32) {
+    let padding = padding.clamp(0.0, 4.0);
+    let padded_w = face.width as f32 * (1.0 + 2.0 * padding);
+    let padded_h = face.height as f32 * (1.0 + 2.0 * padding);
+    let side = (padded_w.max(padded_h).round() as u32).clamp(1, img_w.min(img_h).max(1));
+
+    let cx = face.x as f32 + face.width as f32 / 2.0;
+    let cy = face.y as f32 + face.height as f32 / 2.0;
+    let max_x = img_w.saturating_sub(side) as f32;
+    let max_y = img_h.saturating_sub(side) as f32;
+    let x = (cx - side as f32 / 2.0).clamp(0.0, max_x).round() as u32;
+    let y = (cy - side as f32 / 2.0).clamp(0.0, max_y).round() as u32;
+    (x, y, side, side)
+}
 
-    if payload.flip_x {
-        out_img = out_img.fliph();
-    }
-    if payload.flip_y {
-        out_img = out_img.flipv();
+/// Detect faces and save padded crops around them as new files (caption copied).
+/// Returns the output paths.
+#[tauri::command]
+pub fn autocrop_to_face(payload: AutocropToFacePayload) -> Result<Vec<String>, CommandError> {
+    let path = PathBuf::from(&payload.image_path);
+    if !path.exists() || !path.is_file() {
+        return Err(CommandError::not_found("Image file not found"));
     }
 
-    let rot = ((payload.rotate_degrees % 360 + 360) % 360) / 90;
-    for _ in 0..rot {
-        out_img = out_img.rotate90();
+    // Detect on the oriented image that gets cropped, so regions match its pixels.
+    let img = open_oriented(&path)?;
+    let mut faces = detect_faces_in(&img);
+    if faces.is_empty() {
+        return Err(CommandError::not_found("No faces detected"));
     }
-
-    // Optional: resize to training size (square) for LoRA
-    if let Some(sz) = payload.output_size.filter(|&s| s >= 64 && s <= 2048) {
-        out_img = out_img.resize(sz, sz, FilterType::Triangle);
+    let area = |f: &FaceRegion| f.width as u64 * f.height as u64;
+    let chosen = match payload.strategy {
+        FaceCropStrategy::MostConfident => faces
+            .iter()
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence).then(area(a).cmp(&area(b)))),
+        FaceCropStrategy::Largest => faces
+            .iter()
+            .max_by(|a, b| area(a).cmp(&area(b)).then(a.confidence.total_cmp(&b.confidence))),
+        FaceCropStrategy::All => None,
+    };
+    if let Some(face) = chosen.cloned() {
+        faces = vec![face];
     }
 
+    let (img_w, img_h) = (img.width(), img.height());
     let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
     let ext = path
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("png");
-    let out_path: PathBuf = if payload.save_as_new {
-        let parent = path.parent().unwrap_or_else(|| path.as_path());
-        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
-        let mut n = 1u32;
-        loop {
-            let name = format!("{}_{}_crop.{}", stem, n, ext);
-            let candidate = parent.join(&name);
-            if !candidate.exists() {
-                break candidate;
-            }
-            n += 1;
-            if n > 9999 {
-                return Err("Could not create unique filename for new image".to_string());
+    let caption_ext = caption_extension_for_image(&path);
+
+    let mut output_paths = Vec::new();
+    let mut next_face = 1u32;
+    for face in &faces {
+        let (x, y, cw, ch) = face_crop_rect(face, payload.padding, img_w, img_h);
+        let mut out_img = crop_region(&img, x, y, cw, ch, format);
+        if let Some(sz) = payload.output_size.filter(|&s| (64..=2048).contains(&s)) {
+            out_img = out_img.resize(sz, sz, FilterType::Triangle);
+        }
+
+        // Outputs always take a free name, so earlier crops are never overwritten.
+        let out_path = match payload.strategy {
+            FaceCropStrategy::All => next_face_crop_path(&path, ext, &mut next_face)?,
+            _ => unique_crop_path(&path, ext)?,
+        };
+        let mut file = std::io::BufWriter::new(
+            std::fs::File::create(&out_path)?,
+        );
+        out_img
+            .write_to(&mut file, format)
+            ?;
+        copy_caption(&path, &out_path, &caption_ext);
+
+        output_paths.push(out_path.to_string_lossy().into_owned());
+    }
+
+    Ok(output_paths)
+}
+
+const DEFAULT_BORDER_TOLERANCE: u8 = 16;
+/// Share of pixels in a border line allowed to differ (JPEG noise, watermarks on the bar).
+const BORDER_OUTLIER_RATIO: f32 = 0.01;
+
+/// Content rect (x, y, width, height) after removing uniform-color margins. Each edge is scanned
+/// inward while lines stay within `tolerance` (per channel) of that side's corner color.
+/// None if there is no border or the whole image is uniform.
+fn detect_border_rect(img: &image::DynamicImage, tolerance: u8) -> Option<(u32, u32, u32, u32)> {
+    let rgb = img.to_rgb8();
+    let (w, h) = rgb.dimensions();
+    if w == 0 || h == 0 {
+        return None;
+    }
+    let near = |a: &image::Rgb<u8>, b: &image::Rgb<u8>| {
+        a.0.iter().zip(b.0.iter()).all(|(x, y)| x.abs_diff(*y) <= tolerance)
+    };
+    let line_is_border = |pixels: &mut dyn Iterator<Item = (u32, u32)>, reference: &image::Rgb<u8>| {
+        let (mut total, mut off) = (0usize, 0usize);
+        for (x, y) in pixels {
+            total += 1;
+            if !near(rgb.get_pixel(x, y), reference) {
+                off += 1;
             }
         }
-    } else {
-        path.clone()
+        off as f32 <= total as f32 * BORDER_OUTLIER_RATIO
     };
 
-    let mut file = std::io::BufWriter::new(
-        std::fs::File::create(&out_path).map_err(|e| e.to_string())?,
-    );
-    out_img
-        .write_to(&mut file, format)
-        .map_err(|e| e.to_string())?;
+    let top_left = *rgb.get_pixel(0, 0);
+    let bottom_right = *rgb.get_pixel(w - 1, h - 1);
+
+    let top = (0..h)
+        .find(|&y| !line_is_border(&mut (0..w).map(|x| (x, y)), &top_left))?;
+    let bottom = (top..h)
+        .rev()
+        .find(|&y| !line_is_border(&mut (0..w).map(|x| (x, y)), &bottom_right))
+        .unwrap_or(top);
+    let left = (0..w)
+        .find(|&x| !line_is_border(&mut (top..=bottom).map(|y| (x, y)), &top_left))
+        .unwrap_or(0);
+    let right = (left..w)
+        .rev()
+        .find(|&x| !line_is_border(&mut (top..=bottom).map(|y| (x, y)), &bottom_right))
+        .unwrap_or(left);
+
+    let rect = (left, top, right - left + 1, bottom - top + 1);
+    (rect != (0, 0, w, h)).then_some(rect)
+}
 
-    // When saving as new, copy the source caption to the new image so LoRA workflow keeps tags
-    if payload.save_as_new {
-        let caption_path = path.with_extension("txt");
-        if caption_path.exists() {
-            if let Ok(content) = fs::read_to_string(&caption_path) {
-                let out_txt = out_path.with_extension("txt");
-                let _ = fs::write(out_txt, content.trim());
-            }
+#[derive(Debug, Deserialize)]
+pub struct TrimBordersPayload {
+    pub image_path: String,
+    /// Max per-channel difference from the edge color still counted as border (default 16).
+    #[serde(default)]
+    pub tolerance: Option<u8>,
+    /// Crop the image in place; otherwise only detect.
+    #[serde(default)]
+    pub apply: bool,
+    /// With apply, keep the untouched source in `.lora-studio/originals/` first.
+    #[serde(default)]
+    pub backup_original: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrimBordersResult {
+    /// False if no border was found (or the image is a single flat color).
+    pub trimmed: bool,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Detect solid borders/letterboxing and return the content rect, optionally cropping it in place.
+#[tauri::command]
+pub fn trim_borders(payload: TrimBordersPayload) -> Result<TrimBordersResult, CommandError> {
+    let path = PathBuf::from(&payload.image_path);
+    if !path.exists() || !path.is_file() {
+        return Err(CommandError::not_found("Image file not found"));
+    }
+    let img = open_oriented(&path)?;
+    let tolerance = payload.tolerance.unwrap_or(DEFAULT_BORDER_TOLERANCE);
+    let Some((x, y, width, height)) = detect_border_rect(&img, tolerance) else {
+        return Ok(TrimBordersResult {
+            trimmed: false,
+            x: 0,
+            y: 0,
+            width: img.width(),
+            height: img.height(),
+        });
+    };
+
+    if payload.apply {
+        if payload.backup_original {
+            backup_original(&path)?;
         }
+        let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
+        let out_img = crop_region(&img, x, y, width, height, format);
+        let mut file = std::io::BufWriter::new(
+            std::fs::File::create(&path)?,
+        );
+        out_img
+            .write_to(&mut file, format)
+            ?;
     }
 
-    Ok(if payload.save_as_new {
-        Some(out_path.to_string_lossy().into_owned())
-    } else {
-        None
+    Ok(TrimBordersResult {
+        trimmed: true,
+        x,
+        y,
+        width,
+        height,
     })
 }
 
@@ -237,6 +1303,10 @@ pub enum BatchResizeMode {
     Resize,
     CenterCrop,
     Fit,
+    /// Resize to the aspect-ratio bucket nearest the image (see nearest_bucket), cropping the overflow.
+    Bucket,
+    /// Square crop placed where the most edge energy is (see smart_crop_rect), then resized to target.
+    SmartCrop,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -245,6 +1315,86 @@ pub struct BatchResizePayload {
     pub target_size: u32,
     pub mode: BatchResizeMode,
     pub output_folder: String,
+    /// Allowed (width, height) buckets for Bucket mode. If unset, derived from target_size (see derive_buckets).
+    #[serde(default)]
+    pub buckets: Option<Vec<(u32, u32)>>,
+    /// Grid step used when deriving buckets from target_size (default 64).
+    #[serde(default)]
+    pub bucket_step: Option<u32>,
+    /// Transcode every output to this format. If unset, each image keeps its source format.
+    #[serde(default)]
+    pub output_format: Option<ResizeOutputFormat>,
+    /// JPEG quality 1-100 for JPEG outputs (default 90).
+    #[serde(default)]
+    pub jpeg_quality: Option<u8>,
+    /// SmartCrop: also run face detection and favor windows containing the largest face.
+    #[serde(default)]
+    pub smart_crop_faces: bool,
+    /// Remove solid borders/letterboxing (see trim_borders) before resizing.
+    #[serde(default)]
+    pub trim_borders: bool,
+    /// Composite transparent images onto this RGB color before resizing.
+    #[serde(default)]
+    pub flatten_background: Option<[u8; 3]>,
+    /// Stretch each color channel's histogram to the full range (clipping 0.5% at each end)
+    /// before resizing. Takes precedence over auto_contrast.
+    #[serde(default)]
+    pub auto_levels: bool,
+    /// Stretch contrast from the luminance histogram, keeping colors' hue.
+    #[serde(default)]
+    pub auto_contrast: bool,
+    /// How output files are named (default: 0001, 0002, ... by input position).
+    #[serde(default)]
+    pub naming: BatchResizeNaming,
+    /// Replace outputs whose name is already used in output_folder. When false (default), Index
+    /// naming skips those images and OriginalStem adds a numeric suffix.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchResizeNaming {
+    /// Zero-padded input position (0001, 0002, ...).
+    #[default]
+    Index,
+    /// Zero-padded numbers continuing after the highest numeric name already in output_folder.
+    ContinueIndex,
+    /// Source file stem, with _1, _2, ... appended on collision.
+    OriginalStem,
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeOutputFormat {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl ResizeOutputFormat {
+    fn image_format(self) -> ImageFormat {
+        match self {
+            Self::Jpeg => ImageFormat::Jpeg,
+            Self::Png => ImageFormat::Png,
+            Self::Webp => ImageFormat::WebP,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct BucketAssignment {
+    pub output_path: String,
+    pub width: u32,
+    pub height: u32,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -253,48 +1403,299 @@ pub struct BatchResizeResult {
     pub skipped_count: usize,
     pub output_paths: Vec<String>,
     pub error: Option<String>,
+    /// Images not written because their output name already existed (included in skipped_count).
+    pub skipped_existing_count: usize,
+    /// Bucket each output landed in (Bucket mode only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buckets: Option<Vec<BucketAssignment>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResizeProgress {
+    /// Images finished so far (completion order, not input order).
+    pub current: u32,
+    pub total: u32,
+    pub current_file: String,
+}
+
+const DEFAULT_BUCKET_STEP: u32 = 64;
+
+/// Output file stem (without extension) for each input, or None to skip because the name is taken.
+/// Names are compared by stem so outputs with different extensions can't share a caption file.
+fn plan_output_stems(
+    image_paths: &[String],
+    out_dir: &Path,
+    naming: BatchResizeNaming,
+    overwrite: bool,
+) -> Vec<Option<String>> {
+    let mut taken: HashSet<String> = fs::read_dir(out_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let path = e.path();
+                    let stem = path.file_stem()?.to_str()?.to_lowercase();
+                    Some(stem)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    match naming {
+        BatchResizeNaming::Index => (0..image_paths.len())
+            .map(|i| {
+                let stem = format!("{:04}", i + 1);
+                (overwrite || !taken.contains(&stem)).then_some(stem)
+            })
+            .collect(),
+        BatchResizeNaming::ContinueIndex => {
+            let start = taken
+                .iter()
+                .filter_map(|stem| stem.parse::<u32>().ok())
+                .max()
+                .unwrap_or(0);
+            (0..image_paths.len())
+                .map(|i| Some(format!("{:04}", start as usize + i + 1)))
+                .collect()
+        }
+        BatchResizeNaming::OriginalStem => image_paths
+            .iter()
+            .map(|p| {
+                let stem = Path::new(p)
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("image")
+                    .to_string();
+                let mut candidate = stem.clone();
+                let mut n = 1;
+                while !overwrite && taken.contains(&candidate.to_lowercase()) {
+                    candidate = format!("{}_{}", stem, n);
+                    n += 1;
+                }
+                taken.insert(candidate.to_lowercase());
+                Some(candidate)
+            })
+            .collect(),
+    }
+}
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// A resized output path and the bucket it landed in (Bucket mode only).
+type ResizeOutcome = (String, Option<(u32, u32)>);
+
+/// Encode a resize output. JPEG uses the given quality and drops alpha; WebP (lossless) needs 8-bit RGB(A).
+fn write_resized(
+    img: &image::DynamicImage,
+    out_path: &Path,
+    format: ImageFormat,
+    jpeg_quality: u8,
+) -> Result<(), String> {
+    let file = fs::File::create(out_path).map_err(|e| e.to_string())?;
+    let mut writer = std::io::BufWriter::new(file);
+    match format {
+        ImageFormat::Jpeg => {
+            let encoder =
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, jpeg_quality);
+            image::DynamicImage::ImageRgb8(img.to_rgb8())
+                .write_with_encoder(encoder)
+                .map_err(|e| e.to_string())
+        }
+        ImageFormat::WebP => {
+            let rgba = if img.color().has_alpha() {
+                image::DynamicImage::ImageRgba8(img.to_rgba8())
+            } else {
+                image::DynamicImage::ImageRgb8(img.to_rgb8())
+            };
+            rgba.write_to(&mut writer, format).map_err(|e| e.to_string())
+        }
+        _ => img.write_to(&mut writer, format).map_err(|e| e.to_string()),
+    }
+}
+
+/// Derive aspect-ratio buckets the same way the frontend does (src/lib/buckets.ts computeBuckets):
+/// every (w, h) on a `step` grid between target/2 and target*3/2 whose area is within 15% of
+/// target², keeping the first bucket per ratio (rounded to 2 decimals), sorted portrait to landscape.
+fn derive_buckets(target: u32, step: u32) -> Vec<(u32, u32)> {
+    let step = step.max(8);
+    let min_side = (target / 2).max(step);
+    let max_side = target + target / 2;
+    let base_pixels = target as f64 * target as f64;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut buckets = Vec::new();
+    for w in (min_side..=max_side).step_by(step as usize) {
+        for h in (min_side..=max_side).step_by(step as usize) {
+            let deviation = (w as f64 * h as f64 - base_pixels).abs() / base_pixels;
+            if deviation < 0.15 && seen.insert(format!("{:.2}", w as f64 / h as f64)) {
+                buckets.push((w, h));
+            }
+        }
+    }
+    buckets.sort_by(|a, b| {
+        let ra = a.0 as f64 / a.1 as f64;
+        let rb = b.0 as f64 / b.1 as f64;
+        ra.partial_cmp(&rb).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    buckets
+}
+
+/// Bucket selection rule: the bucket whose width/height ratio has the smallest absolute difference
+/// from the image's ratio; on a tie the earlier bucket in the list wins.
+fn nearest_bucket(buckets: &[(u32, u32)], w: u32, h: u32) -> (u32, u32) {
+    let ratio = w as f64 / h.max(1) as f64;
+    let mut best = buckets[0];
+    let mut best_diff = f64::MAX;
+    for &(bw, bh) in buckets {
+        let diff = (bw as f64 / bh as f64 - ratio).abs();
+        if diff < best_diff {
+            best_diff = diff;
+            best = (bw, bh);
+        }
+    }
+    best
+}
+
+/// Longest side of the downscaled copy used for the SmartCrop energy map.
+const SMART_CROP_ANALYSIS_SIZE: u32 = 256;
+/// Extra energy given to the face span, as a multiple of the mean per-line energy.
+const SMART_CROP_FACE_WEIGHT: f64 = 4.0;
+
+/// Square crop (side = the short side) sliding along the long axis to keep the most gradient
+/// energy. The energy map is computed on a downscaled grayscale copy; `face` (in full-size pixel
+/// coordinates) adds a bonus so its span is preferred.
+fn smart_crop_rect(img: &image::DynamicImage, face: Option<&FaceRegion>) -> (u32, u32, u32, u32) {
+    let (w, h) = (img.width(), img.height());
+    let side = w.min(h);
+    if w == h {
+        return (0, 0, side, side);
+    }
+    let landscape = w > h;
+
+    let small = img
+        .thumbnail(SMART_CROP_ANALYSIS_SIZE, SMART_CROP_ANALYSIS_SIZE)
+        .to_luma8();
+    let (sw, sh) = small.dimensions();
+    let long_small = if landscape { sw } else { sh } as usize;
+
+    // Energy per line across the long axis: sum of |dx| + |dy| over the short axis.
+    let mut profile = vec![0f64; long_small];
+    for y in 0..sh {
+        for x in 0..sw {
+            let p = small.get_pixel(x, y)[0] as i32;
+            let dx = if x + 1 < sw { (small.get_pixel(x + 1, y)[0] as i32 - p).abs() } else { 0 };
+            let dy = if y + 1 < sh { (small.get_pixel(x, y + 1)[0] as i32 - p).abs() } else { 0 };
+            profile[if landscape { x } else { y } as usize] += (dx + dy) as f64;
+        }
+    }
+
+    let long_full = if landscape { w } else { h };
+    let scale = long_small as f64 / long_full as f64;
+    if let Some(face) = face {
+        let mean = profile.iter().sum::<f64>() / long_small.max(1) as f64;
+        let (start, len) = if landscape { (face.x, face.width) } else { (face.y, face.height) };
+        let from = ((start as f64 * scale) as usize).min(long_small);
+        let to = (((start + len) as f64 * scale).ceil() as usize).min(long_small);
+        for v in &mut profile[from..to] {
+            *v += mean * SMART_CROP_FACE_WEIGHT;
+        }
+    }
+
+    // Best window of the crop's length (in downscaled units) via prefix sums.
+    let window = ((side as f64 * scale).round() as usize).clamp(1, long_small.max(1));
+    let mut prefix = vec![0f64; long_small + 1];
+    for (i, v) in profile.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + v;
+    }
+    let best_start = (0..=long_small.saturating_sub(window))
+        .max_by(|&a, &b| {
+            let ea = prefix[a + window] - prefix[a];
+            let eb = prefix[b + window] - prefix[b];
+            ea.total_cmp(&eb)
+        })
+        .unwrap_or(0);
+
+    let offset = ((best_start as f64 / scale).round() as u32).min(long_full - side);
+    if landscape {
+        (offset, 0, side, side)
+    } else {
+        (0, offset, side, side)
+    }
+}
+
+/// Scale so the image covers the bucket, then center-crop the overflow (minimal crop, no padding).
+fn fit_to_bucket(img: &image::DynamicImage, bw: u32, bh: u32) -> image::DynamicImage {
+    let (w, h) = (img.width().max(1), img.height().max(1));
+    let scale = (bw as f64 / w as f64).max(bh as f64 / h as f64);
+    let new_w = ((w as f64 * scale).ceil() as u32).max(bw);
+    let new_h = ((h as f64 * scale).ceil() as u32).max(bh);
+    let scaled = img.resize_exact(new_w, new_h, FilterType::Triangle);
+    scaled.crop_imm((new_w - bw) / 2, (new_h - bh) / 2, bw, bh)
 }
 
 /// Batch resize/preprocess images to target size. Outputs to specified folder, copies captions.
+/// Emits `batch-resize-progress` as each image completes.
 #[tauri::command]
-pub fn batch_resize(payload: BatchResizePayload) -> Result<BatchResizeResult, String> {
+pub fn batch_resize(
+    payload: BatchResizePayload,
+    window: tauri::Window,
+) -> Result<BatchResizeResult, CommandError> {
     if payload.target_size < 64 || payload.target_size > 2048 {
-        return Err("Target size must be between 64 and 2048".to_string());
+        return Err(CommandError::invalid_input("Target size must be between 64 and 2048"));
     }
     let target = payload.target_size;
 
+    let buckets: Vec<(u32, u32)> = match &payload.buckets {
+        Some(list) => list.iter().copied().filter(|&(w, h)| w > 0 && h > 0).collect(),
+        None => derive_buckets(target, payload.bucket_step.unwrap_or(DEFAULT_BUCKET_STEP)),
+    };
+    let bucket_mode = matches!(payload.mode, BatchResizeMode::Bucket);
+    if bucket_mode && buckets.is_empty() {
+        return Err(CommandError::invalid_input("No valid buckets for bucket mode"));
+    }
+
+    let jpeg_quality = payload
+        .jpeg_quality
+        .unwrap_or(DEFAULT_JPEG_QUALITY)
+        .clamp(1, 100);
+
     let out_dir = PathBuf::from(&payload.output_folder);
-    fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    fs::create_dir_all(&out_dir)?;
 
-    let mut processed = 0usize;
-    let mut skipped = 0usize;
-    let mut output_paths = Vec::new();
+    // Output names are planned up front, so results stay deterministic when processed in parallel.
+    let stems = plan_output_stems(&payload.image_paths, &out_dir, payload.naming, payload.overwrite);
+    let skipped_existing = stems.iter().filter(|s| s.is_none()).count();
+    let project = ProjectLookup::default();
 
-    for (i, img_path_str) in payload.image_paths.iter().enumerate() {
+    let resize_one = |i: usize, img_path_str: &String| -> Option<ResizeOutcome> {
+        let base = stems[i].as_deref()?;
         let path = PathBuf::from(img_path_str);
         if !path.exists() || !path.is_file() {
-            skipped += 1;
-            continue;
+            return None;
         }
 
-        let ext = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("png");
-        let new_name = format!("{:04}.{}", i + 1, ext);
-        let out_img = out_dir.join(&new_name);
-        let base = new_name.rsplit_once('.').map(|n| n.0).unwrap_or(&new_name);
-        let out_txt = out_dir.join(format!("{}.txt", base));
-
-        let img = match image::open(&path) {
-            Ok(i) => i,
-            Err(_) => {
-                skipped += 1;
-                continue;
-            }
+        let ext = match payload.output_format {
+            Some(fmt) => fmt.extension(),
+            None => path.extension().and_then(|e| e.to_str()).unwrap_or("png"),
         };
+        let out_img = out_dir.join(format!("{}.{}", base, ext));
+        let caption_ext = project.caption_extension(&path);
+        let out_txt = out_dir.join(format!("{}.{}", base, caption_ext));
+
+        let mut img = open_oriented(&path).ok()?;
+        if payload.trim_borders {
+            if let Some((x, y, cw, ch)) = detect_border_rect(&img, DEFAULT_BORDER_TOLERANCE) {
+                img = img.crop_imm(x, y, cw, ch);
+            }
+        }
+        if let Some(bg) = payload.flatten_background {
+            img = flatten_alpha(img, bg);
+        }
+        if payload.auto_levels || payload.auto_contrast {
+            img = stretch_levels(img, payload.auto_levels);
+        }
 
         let (w, h) = (img.width(), img.height());
+        let mut landed_bucket = None;
         let out_img_dyn: image::DynamicImage = match &payload.mode {
             BatchResizeMode::Resize => img.resize(target, target, FilterType::Triangle),
             BatchResizeMode::CenterCrop => {
@@ -302,8 +1703,8 @@ pub fn batch_resize(payload: BatchResizePayload) -> Result<BatchResizeResult, St
                 let crop_size = min_side.min(target);
                 let x = (w - crop_size) / 2;
                 let y = (h - crop_size) / 2;
-                let cropped = img.crop_imm(x, y, crop_size, crop_size);
-                let cropped_dyn = image::DynamicImage::from(cropped.to_rgb8());
+                let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
+                let cropped_dyn = crop_region(&img, x, y, crop_size, crop_size, format);
                 cropped_dyn.resize(target, target, FilterType::Triangle)
             }
             BatchResizeMode::Fit => {
@@ -317,48 +1718,234 @@ pub fn batch_resize(payload: BatchResizePayload) -> Result<BatchResizeResult, St
                     img.resize(new_w, new_h, FilterType::Triangle)
                 }
             }
+            BatchResizeMode::Bucket => {
+                let (bw, bh) = nearest_bucket(&buckets, w, h);
+                landed_bucket = Some((bw, bh));
+                fit_to_bucket(&img, bw, bh)
+            }
+            BatchResizeMode::SmartCrop => {
+                // Detect on the oriented image smart_crop_rect works on, so the face biases
+                // the window in the same coordinates.
+                let face = if payload.smart_crop_faces {
+                    detect_faces_in(&img)
+                        .into_iter()
+                        .max_by_key(|f| f.width as u64 * f.height as u64)
+                } else {
+                    None
+                };
+                let (x, y, cw, ch) = smart_crop_rect(&img, face.as_ref());
+                let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
+                crop_region(&img, x, y, cw, ch, format).resize(target, target, FilterType::Triangle)
+            }
         };
 
-        let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
-        let mut out_file = fs::File::create(&out_img).map_err(|e| e.to_string())?;
-        if out_img_dyn.write_to(&mut out_file, format).is_err() {
-            skipped += 1;
-            continue;
-        }
+        let format = match payload.output_format {
+            Some(fmt) => fmt.image_format(),
+            None => ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png),
+        };
+        write_resized(&out_img_dyn, &out_img, format, jpeg_quality).ok()?;
 
         // Copy caption if exists
-        let caption_path = path.with_extension("txt");
+        let caption_path = path.with_extension(&caption_ext);
         if caption_path.exists() {
-            if let Ok(content) = fs::read_to_string(&caption_path) {
+            if let Ok(content) = read_caption_text(&caption_path) {
                 let _ = fs::write(&out_txt, content.trim());
             }
         }
 
-        output_paths.push(out_img.to_string_lossy().into_owned());
-        processed += 1;
+        Some((out_img.to_string_lossy().into_owned(), landed_bucket))
+    };
+
+    let total = payload.image_paths.len() as u32;
+    let completed = AtomicU32::new(0);
+    let outcomes: Vec<Option<ResizeOutcome>> = payload
+        .image_paths
+        .par_iter()
+        .enumerate()
+        .map(|(i, img_path_str)| {
+            let outcome = resize_one(i, img_path_str);
+            let current = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            let _ = window.emit(
+                "batch-resize-progress",
+                BatchResizeProgress {
+                    current,
+                    total,
+                    current_file: img_path_str.clone(),
+                },
+            );
+            outcome
+        })
+        .collect();
+
+    let mut skipped = 0usize;
+    let mut output_paths = Vec::new();
+    let mut assignments = Vec::new();
+    for outcome in outcomes {
+        let Some((output_path, landed_bucket)) = outcome else {
+            skipped += 1;
+            continue;
+        };
+        if let Some((bw, bh)) = landed_bucket {
+            assignments.push(BucketAssignment {
+                output_path: output_path.clone(),
+                width: bw,
+                height: bh,
+            });
+        }
+        output_paths.push(output_path);
     }
+    let processed = output_paths.len();
 
     Ok(BatchResizeResult {
         processed_count: processed,
         skipped_count: skipped,
         output_paths,
         error: None,
+        skipped_existing_count: skipped_existing,
+        buckets: bucket_mode.then_some(assignments),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertImagesPayload {
+    pub image_paths: Vec<String>,
+    pub target_format: ResizeOutputFormat,
+    /// JPEG quality 1-100 (default 90).
+    #[serde(default)]
+    pub jpeg_quality: Option<u8>,
+    /// Write converted files here (same file names, new extension; names already taken in the
+    /// folder or by another image of the batch get a `_1`, `_2`... suffix). If unset, convert next
+    /// to the source.
+    #[serde(default)]
+    pub output_folder: Option<String>,
+    /// Remove each source image after a successful in-place conversion (ignored with output_folder).
+    #[serde(default)]
+    pub delete_originals: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertImagesResult {
+    pub converted_count: usize,
+    /// Missing or unreadable files, and in-place conversions whose target name is already taken
+    /// (on disk or by an earlier image of the batch, e.g. `a.png` and `a.webp` to JPEG).
+    pub skipped_count: usize,
+    pub output_paths: Vec<String>,
+}
+
+/// Transcode images to another format without resizing, copying captions alongside.
+#[tauri::command]
+pub fn convert_images(payload: ConvertImagesPayload) -> Result<ConvertImagesResult, CommandError> {
+    let jpeg_quality = payload
+        .jpeg_quality
+        .unwrap_or(DEFAULT_JPEG_QUALITY)
+        .clamp(1, 100);
+    let out_dir = payload.output_folder.as_ref().map(PathBuf::from);
+    if let Some(dir) = &out_dir {
+        fs::create_dir_all(dir)?;
+    }
+    let ext = payload.target_format.extension();
+
+    // Output paths are planned up front so no two images of the batch write the same file.
+    let targets: Vec<Option<PathBuf>> = match &out_dir {
+        Some(dir) => {
+            let naming = BatchResizeNaming::OriginalStem;
+            plan_output_stems(&payload.image_paths, dir, naming, false)
+                .into_iter()
+                .map(|stem| stem.map(|stem| dir.join(format!("{}.{}", stem, ext))))
+                .collect()
+        }
+        None => {
+            let mut claimed: HashSet<PathBuf> = HashSet::new();
+            payload
+                .image_paths
+                .iter()
+                .map(|img_path_str| {
+                    let path = PathBuf::from(img_path_str);
+                    let out_path = path.with_extension(ext);
+                    if out_path == path {
+                        return Some(out_path);
+                    }
+                    (!out_path.exists() && claimed.insert(out_path.clone())).then_some(out_path)
+                })
+                .collect()
+        }
+    };
+
+    let project = ProjectLookup::default();
+    let outcomes: Vec<Option<String>> = payload
+        .image_paths
+        .par_iter()
+        .zip(targets)
+        .map(|(img_path_str, out_path)| {
+            let path = PathBuf::from(img_path_str);
+            let out_path = out_path?;
+            if !path.is_file() {
+                return None;
+            }
+            let in_place = out_dir.is_none();
+
+            let img = open_oriented(&path).ok()?;
+            write_resized(&img, &out_path, payload.target_format.image_format(), jpeg_quality).ok()?;
+
+            if !in_place {
+                copy_caption(&path, &out_path, &project.caption_extension(&path));
+            } else if payload.delete_originals && out_path != path {
+                let _ = fs::remove_file(&path);
+            }
+            Some(out_path.to_string_lossy().into_owned())
+        })
+        .collect();
+
+    let output_paths: Vec<String> = outcomes.iter().flatten().cloned().collect();
+    Ok(ConvertImagesResult {
+        converted_count: output_paths.len(),
+        skipped_count: outcomes.len() - output_paths.len(),
+        output_paths,
     })
 }
 
-/// Delete an image file and its caption .txt from disk.
+#[derive(Debug, Serialize)]
+pub struct DeleteImageResult {
+    /// True if the files went to the OS trash; false if they were removed permanently.
+    pub trashed: bool,
+}
+
+/// Whether `trash` supports this platform. Where it does not, deletes are always permanent.
+const PLATFORM_HAS_TRASH: bool = cfg!(any(
+    target_os = "windows",
+    target_os = "macos",
+    all(unix, not(target_os = "ios"), not(target_os = "android"))
+));
+
+/// Delete an image file and its caption file. Moves both to the OS trash together unless
+/// `permanent` is true. If the move to trash fails, nothing is deleted and a `trash_failed` error
+/// is returned so the UI can ask before retrying with `permanent`. Only platforms with no trash
+/// at all delete permanently without being asked.
 #[tauri::command]
-pub fn delete_image(image_path: String) -> Result<(), String> {
+pub fn delete_image(image_path: String, permanent: Option<bool>) -> Result<DeleteImageResult, CommandError> {
     let path = PathBuf::from(&image_path);
     if !path.exists() || !path.is_file() {
-        return Err("Image file not found".to_string());
+        return Err(CommandError::not_found("Image file not found"));
     }
-    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
-    let txt_path = path.with_extension("txt");
-    if txt_path.exists() && txt_path.is_file() {
+    let txt_path = path.with_extension(caption_extension_for_image(&path));
+    let has_caption = txt_path.exists() && txt_path.is_file();
+
+    if !permanent.unwrap_or(false) && PLATFORM_HAS_TRASH {
+        let mut targets = vec![path.clone()];
+        if has_caption {
+            targets.push(txt_path.clone());
+        }
+        trash::delete_all(&targets).map_err(|e| {
+            CommandError::new(ErrorCode::TrashFailed, format!("Could not move to trash: {}", e))
+        })?;
+        return Ok(DeleteImageResult { trashed: true });
+    }
+
+    std::fs::remove_file(&path)?;
+    if has_caption {
         let _ = std::fs::remove_file(&txt_path);
     }
-    Ok(())
+    Ok(DeleteImageResult { trashed: false })
 }
 
 #[derive(Debug, Deserialize)]
@@ -368,32 +1955,118 @@ pub struct CropRect {
     pub width: u32,
     pub height: u32,
     pub suffix: String, // "_full", "_med", "_close"
+    /// Per-crop output dimensions, overriding the payload's output_width/output_height.
+    #[serde(default)]
+    pub output_width: Option<u32>,
+    #[serde(default)]
+    pub output_height: Option<u32>,
+}
+
+/// One crop of a multi_crop preset, as fractions of the image size.
+struct PresetRect {
+    suffix: &'static str,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+const fn preset_rect(suffix: &'static str, x: f32, y: f32, width: f32, height: f32) -> PresetRect {
+    PresetRect {
+        suffix,
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// Named multi_crop presets. Assumes an upright subject roughly centered horizontally.
+const MULTI_CROP_PRESETS: &[(&str, &[PresetRect])] = &[
+    (
+        "character_sheet",
+        &[
+            preset_rect("_full", 0.0, 0.0, 1.0, 1.0),
+            preset_rect("_upper", 0.1, 0.0, 0.8, 0.55),
+            preset_rect("_face", 0.3, 0.02, 0.4, 0.28),
+        ],
+    ),
+    (
+        "zoom_levels",
+        &[
+            preset_rect("_full", 0.0, 0.0, 1.0, 1.0),
+            preset_rect("_med", 0.15, 0.15, 0.7, 0.7),
+            preset_rect("_close", 0.3, 0.3, 0.4, 0.4),
+        ],
+    ),
+];
+
+/// Expand a named preset into pixel crop rects for an image of the given size.
+fn preset_crops(preset: &str, img_w: u32, img_h: u32) -> Result<Vec<CropRect>, String> {
+    let (_, rects) = MULTI_CROP_PRESETS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(preset.trim()))
+        .ok_or_else(|| format!("Unknown crop preset: {}", preset))?;
+    Ok(rects
+        .iter()
+        .map(|r| CropRect {
+            x: (r.x * img_w as f32).round() as u32,
+            y: (r.y * img_h as f32).round() as u32,
+            width: ((r.width * img_w as f32).round() as u32).max(1),
+            height: ((r.height * img_h as f32).round() as u32).max(1),
+            suffix: r.suffix.to_string(),
+            output_width: None,
+            output_height: None,
+        })
+        .collect())
 }
 
 #[derive(Debug, Deserialize)]
 pub struct MultiCropPayload {
     pub image_path: String,
+    #[serde(default)]
     pub crops: Vec<CropRect>,
+    /// Named preset ("character_sheet", "zoom_levels") computed from the image size; replaces crops.
+    #[serde(default)]
+    pub preset: Option<String>,
     #[serde(default)]
     pub flip_x: bool,
     #[serde(default)]
     pub flip_y: bool,
     #[serde(default)]
-    pub rotate_degrees: i32,
+    pub rotate_degrees: f32,
+    #[serde(default)]
+    pub rotate_fill: Option<[u8; 3]>,
+    #[serde(default)]
+    pub rotate_auto_crop: bool,
     #[serde(default)]
     pub output_size: Option<u32>,
+    #[serde(default)]
+    pub output_width: Option<u32>,
+    #[serde(default)]
+    pub output_height: Option<u32>,
+    #[serde(default)]
+    pub flatten_background: Option<[u8; 3]>,
+    #[serde(default)]
+    pub root_path: Option<String>,
+    /// With root_path, give each new file the source's rating, stars and crop status.
+    #[serde(default)]
+    pub copy_metadata: bool,
 }
 
 /// Crop an image multiple times with different regions, saving each with a suffix.
 /// Returns Vec of output paths.
 #[tauri::command]
-pub fn multi_crop(payload: MultiCropPayload) -> Result<Vec<String>, String> {
+pub fn multi_crop(payload: MultiCropPayload) -> Result<Vec<String>, CommandError> {
     let path = PathBuf::from(&payload.image_path);
     if !path.exists() || !path.is_file() {
-        return Err("Image file not found".to_string());
+        return Err(CommandError::not_found("Image file not found"));
     }
 
-    let img = image::open(&path).map_err(|e| e.to_string())?;
+    let mut img = open_oriented(&path)?;
+    if let Some(bg) = payload.flatten_background {
+        img = flatten_alpha(img, bg);
+    }
     let (img_w, img_h) = (img.width(), img.height());
     let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
     let ext = path
@@ -403,9 +2076,15 @@ pub fn multi_crop(payload: MultiCropPayload) -> Result<Vec<String>, String> {
     let parent = path.parent().unwrap_or_else(|| path.as_path());
     let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
 
+    let crops = match &payload.preset {
+        Some(preset) => preset_crops(preset, img_w, img_h)?,
+        None => payload.crops,
+    };
+    let caption_ext = caption_extension_for_image(&path);
+
     let mut output_paths = Vec::new();
 
-    for crop in &payload.crops {
+    for crop in &crops {
         let x = crop.x.min(img_w.saturating_sub(1));
         let y = crop.y.min(img_h.saturating_sub(1));
         let cw = crop.width.min(img_w.saturating_sub(x));
@@ -415,8 +2094,7 @@ pub fn multi_crop(payload: MultiCropPayload) -> Result<Vec<String>, String> {
             continue; // skip invalid crops
         }
 
-        let cropped_sub = img.crop_imm(x, y, cw, ch);
-        let mut out_img = image::DynamicImage::from(cropped_sub.to_rgb8());
+        let mut out_img = crop_region(&img, x, y, cw, ch, format);
 
         if payload.flip_x {
             out_img = out_img.fliph();
@@ -425,39 +2103,44 @@ pub fn multi_crop(payload: MultiCropPayload) -> Result<Vec<String>, String> {
             out_img = out_img.flipv();
         }
 
-        let rot = ((payload.rotate_degrees % 360 + 360) % 360) / 90;
-        for _ in 0..rot {
-            out_img = out_img.rotate90();
-        }
+        out_img = rotate_image(
+            out_img,
+            payload.rotate_degrees,
+            payload.rotate_fill,
+            payload.rotate_auto_crop,
+            format,
+        );
 
-        if let Some(sz) = payload.output_size.filter(|&s| s >= 64 && s <= 2048) {
-            out_img = out_img.resize(sz, sz, FilterType::Triangle);
-        }
+        // Per-crop dimensions win as a pair so a crop can't mix its width with the payload's height.
+        let (output_width, output_height) = if crop.output_width.is_some() || crop.output_height.is_some() {
+            (crop.output_width, crop.output_height)
+        } else {
+            (payload.output_width, payload.output_height)
+        };
+        out_img = resize_output(out_img, payload.output_size, output_width, output_height);
 
         let out_name = format!("{}{}.{}", stem, crop.suffix, ext);
         let out_path = parent.join(&out_name);
 
         let mut file = std::io::BufWriter::new(
-            std::fs::File::create(&out_path).map_err(|e| e.to_string())?,
+            std::fs::File::create(&out_path)?,
         );
         out_img
             .write_to(&mut file, format)
-            .map_err(|e| e.to_string())?;
+            ?;
 
         // Copy caption to new file with suffix
-        let caption_path = path.with_extension("txt");
-        if caption_path.exists() {
-            if let Ok(content) = fs::read_to_string(&caption_path) {
-                let out_txt = out_path.with_extension("txt");
-                let _ = fs::write(out_txt, content.trim());
-            }
-        }
+        copy_caption(&path, &out_path, &caption_ext);
 
         output_paths.push(out_path.to_string_lossy().into_owned());
     }
 
     if output_paths.is_empty() {
-        return Err("No valid crops processed".to_string());
+        return Err(CommandError::invalid_input("No valid crops processed"));
+    }
+    if let (true, Some(root)) = (payload.copy_metadata, &payload.root_path) {
+        let outputs: Vec<PathBuf> = output_paths.iter().map(PathBuf::from).collect();
+        copy_image_metadata(root, &path, &outputs)?;
     }
 
     Ok(output_paths)
@@ -468,6 +2151,11 @@ pub struct GetThumbnailsBatchPayload {
     pub paths: Vec<String>,
     #[serde(default)]
     pub size: Option<u32>,
+    #[serde(default)]
+    pub format: ThumbnailFormat,
+    /// JPEG/WebP quality 1-100 (default 75).
+    #[serde(default)]
+    pub quality: Option<u8>,
 }
 
 #[derive(Debug, Serialize)]
@@ -479,8 +2167,9 @@ pub struct ThumbnailResult {
 
 /// Generate thumbnails for multiple images in parallel
 #[tauri::command]
-pub fn get_thumbnails_batch(payload: GetThumbnailsBatchPayload) -> Result<Vec<ThumbnailResult>, String> {
+pub fn get_thumbnails_batch(payload: GetThumbnailsBatchPayload) -> Result<Vec<ThumbnailResult>, CommandError> {
     let size = payload.size.unwrap_or(THUMB_SIZE).min(512);
+    let encoding = ThumbnailEncoding::new(payload.format, payload.quality);
     let cache_dir = thumbnail_cache_dir()?;
 
     let results: Vec<ThumbnailResult> = payload
@@ -498,18 +2187,18 @@ pub fn get_thumbnails_batch(payload: GetThumbnailsBatchPayload) -> Result<Vec<Th
             }
 
             // Try to get from cache
-            match thumbnail_cache_key(&path, size) {
+            match thumbnail_cache_key(&path, size, encoding) {
                 Ok(key) => {
-                    let cache_path = cache_dir.join(format!("{}.jpg", key));
+                    let cache_path = encoding.cache_path(&cache_dir, &key);
                     
                     if cache_path.exists() && cache_path.is_file() {
                         if let Ok(mut f) = fs::File::open(&cache_path) {
                             let mut buf = Vec::new();
                             if f.read_to_end(&mut buf).is_ok() {
-                                let b64 = BASE64.encode(&buf);
+                                touch_cache_file(&cache_path);
                                 return ThumbnailResult {
                                     path: path_str.clone(),
-                                    data_url: Some(format!("data:image/jpeg;base64,{b64}")),
+                                    data_url: Some(encoding.data_url(&buf)),
                                     error: None,
                                 };
                             }
@@ -517,21 +2206,17 @@ pub fn get_thumbnails_batch(payload: GetThumbnailsBatchPayload) -> Result<Vec<Th
                     }
 
                     // Generate thumbnail
-                    match image::open(&path) {
+                    match open_oriented(&path) {
                         Ok(img) => {
                             let thumb = img.resize(size, size, FilterType::Triangle);
-                            let mut buf = Vec::new();
                             
-                            if thumb.write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg).is_ok() {
+                            if let Ok(buf) = encoding.encode(&thumb) {
                                 // Try to cache
-                                if let Ok(mut f) = fs::File::create(&cache_path) {
-                                    let _ = f.write_all(&buf);
-                                }
+                                store_cached_thumbnail(&cache_dir, &cache_path, &buf);
                                 
-                                let b64 = BASE64.encode(&buf);
                                 ThumbnailResult {
                                     path: path_str.clone(),
-                                    data_url: Some(format!("data:image/jpeg;base64,{b64}")),
+                                    data_url: Some(encoding.data_url(&buf)),
                                     error: None,
                                 }
                             } else {
@@ -2,11 +2,29 @@ use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use futures::stream::{self, StreamExt};
 use image::imageops::FilterType;
 use image::ImageFormat;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::Cursor;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+
+use super::ai_concurrency::AiConcurrencyLimiter;
+use super::captions::{merge_caption_file, read_caption_text, CaptionMergeMode};
+use super::project_config::ProjectLookup;
 
 const DEFAULT_BASE_URL: &str = "http://localhost:1234";
+const CAPTION_TOKEN_EVENT: &str = "caption-token";
+
+/// Vision probe outcomes keyed by "base_url|model"; only definite answers are cached.
+static VISION_PROBE_CACHE: Lazy<Mutex<HashMap<String, Result<(), String>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Cancellation flags for running caption batches, keyed by batch_id.
+static BATCH_CANCEL_FLAGS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -97,19 +115,169 @@ pub struct GenerateCaptionPayload {
     /// If set, resize image so longest side is at most this (reduces payload and inference time).
     #[serde(default)]
     pub max_image_dimension: Option<u32>,
+    /// If true, stream the response and emit caption-token events ({ path, delta }) as tokens arrive.
+    #[serde(default)]
+    pub stream: bool,
+    /// Optional system message sent before the user prompt.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Sampling temperature (default 0.7).
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Stop sequences passed through to the model.
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+    /// Extra leading phrases to strip from the caption (case-insensitive).
+    #[serde(default)]
+    pub strip_prefixes: Option<Vec<String>>,
+    /// Strip common openers like "This image shows" / "The image depicts".
+    #[serde(default)]
+    pub strip_default_boilerplate: bool,
+    /// Reference images sent before the target in the same request (e.g. style examples).
+    /// Each is resized like the target when max_image_dimension is set.
+    #[serde(default)]
+    pub context_image_paths: Option<Vec<String>>,
 }
 
-fn default_max_tokens() -> u32 {
+pub fn default_max_tokens() -> u32 {
     300
 }
 
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+
+/// Openers vision models tend to prepend; removed when strip_default_boilerplate is set.
+const DEFAULT_BOILERPLATE_PREFIXES: &[&str] = &[
+    "this image shows",
+    "this image depicts",
+    "the image shows",
+    "the image depicts",
+    "this picture shows",
+    "the picture shows",
+    "this is an image of",
+    "this is a picture of",
+    "in this image",
+    "in this picture",
+    "an image of",
+    "a picture of",
+];
+
+/// Remove `prefix` from the start of `text` if it matches case-insensitively on a word boundary.
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = &text[prefix.len()..];
+    if rest.chars().next().is_some_and(|c| c.is_alphanumeric()) {
+        return None;
+    }
+    Some(rest)
+}
+
+/// Strip boilerplate openers from a caption; tag-style output (comma-separated, no sentences)
+/// also loses its trailing period.
+pub fn clean_caption(caption: &str, extra_prefixes: &[String], default_boilerplate: bool) -> String {
+    let mut prefixes: Vec<&str> = extra_prefixes
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if default_boilerplate {
+        prefixes.extend(DEFAULT_BOILERPLATE_PREFIXES);
+    }
+    if prefixes.is_empty() {
+        return caption.to_string();
+    }
+
+    let mut text = caption.trim();
+    while let Some(rest) = prefixes
+        .iter()
+        .find_map(|p| strip_prefix_ignore_case(text, p))
+    {
+        text = rest.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ':' | ';' | '-'));
+    }
+
+    let stripped = text.len() != caption.trim().len();
+    let is_tag_style = text.contains(',') && !text.trim_end_matches('.').contains(". ");
+    if is_tag_style {
+        return text.trim_end_matches('.').trim_end().to_string();
+    }
+
+    // Prose that lost its opener ("The image shows a dog...") starts mid-sentence; capitalize it.
+    let mut cleaned = text.to_string();
+    if stripped {
+        if let Some(first) = cleaned.chars().next().filter(|c| c.is_lowercase()) {
+            cleaned = first.to_uppercase().chain(cleaned.chars().skip(1)).collect();
+        }
+    }
+    cleaned
+}
+
 const DEFAULT_TIMEOUT_SECS: u32 = 120;
-const MAX_TIMEOUT_SECS: u32 = 600;
+pub const MAX_TIMEOUT_SECS: u32 = 600;
 
-fn default_timeout_secs() -> u32 {
+pub fn default_timeout_secs() -> u32 {
     DEFAULT_TIMEOUT_SECS
 }
 
+/// Decode an image and re-encode it as base64 JPEG (vision endpoints often only accept JPEG).
+/// If max_dimension is set, resize so the longest side is at most that (reduces payload and inference time).
+pub fn encode_image_jpeg_base64(path: &Path, max_dimension: Option<u32>) -> Result<String, String> {
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let (w, h) = (img.width(), img.height());
+
+    let img = if let Some(max_dim) = max_dimension.filter(|&d| d > 0) {
+        let longest = w.max(h);
+        if longest > max_dim {
+            let scale = max_dim as f32 / longest as f32;
+            let new_w = (w as f32 * scale).round() as u32;
+            let new_h = (h as f32 * scale).round() as u32;
+            let new_w = new_w.max(1);
+            let new_h = new_h.max(1);
+            img.resize(new_w, new_h, FilterType::Triangle)
+        } else {
+            img
+        }
+    } else {
+        img
+    };
+
+    let mut buf = Vec::new();
+    img.write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
+        .map_err(|e| e.to_string())?;
+    Ok(BASE64.encode(&buf))
+}
+
+/// Send a request, retrying once if it timed out. On failure returns a message suitable for CaptionResult.error.
+pub async fn send_with_timeout_retry<F, Fut>(
+    do_request: F,
+    timeout_secs: u32,
+) -> Result<reqwest::Response, String>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+{
+    match do_request().await {
+        Ok(r) => Ok(r),
+        Err(e) => {
+            let err_str = e.to_string();
+            let is_timeout = err_str.contains("timed out") || err_str.contains("timeout");
+            if !is_timeout {
+                return Err(format!("Request failed: {}", e));
+            }
+            // Retry once on timeout
+            do_request().await.map_err(|_| {
+                format!(
+                    "Request timed out after {} seconds (tried 2 times). Try a larger timeout in settings or use smaller images.",
+                    timeout_secs
+                )
+            })
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct CaptionResult {
     pub success: bool,
@@ -117,11 +285,189 @@ pub struct CaptionResult {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct CaptionTokenEvent {
+    path: String,
+    delta: String,
+}
+
+/// Read an OpenAI-style SSE stream ("data: {...}" lines, ending with "data: [DONE]"),
+/// emitting each content delta as a caption-token event. Returns the assembled caption.
+async fn read_streamed_caption(
+    mut response: reqwest::Response,
+    app: &AppHandle,
+    image_path: &str,
+) -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct StreamChunk {
+        choices: Vec<StreamChoice>,
+    }
+
+    #[derive(Deserialize)]
+    struct StreamChoice {
+        #[serde(default)]
+        delta: StreamDelta,
+    }
+
+    #[derive(Deserialize, Default)]
+    struct StreamDelta {
+        #[serde(default)]
+        content: Option<String>,
+    }
+
+    let mut caption = String::new();
+    let mut pending: Vec<u8> = Vec::new();
+
+    while let Some(bytes) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Stream interrupted: {}", e))?
+    {
+        pending.extend_from_slice(&bytes);
+        while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                return Ok(caption.trim().to_string());
+            }
+            let Ok(chunk) = serde_json::from_str::<StreamChunk>(data) else {
+                continue;
+            };
+            let delta: String = chunk
+                .choices
+                .into_iter()
+                .filter_map(|c| c.delta.content)
+                .collect();
+            if !delta.is_empty() {
+                caption.push_str(&delta);
+                let _ = app.emit(
+                    CAPTION_TOKEN_EVENT,
+                    CaptionTokenEvent {
+                        path: image_path.to_string(),
+                        delta,
+                    },
+                );
+            }
+        }
+    }
+
+    Ok(caption.trim().to_string())
+}
+
+/// Check that the selected model accepts images before uploading real ones. LM Studio's
+/// `/api/v0/models/{id}` reports `type: "vlm"` for vision models; that answer is cached for the
+/// session. Other servers get a tiny test image with max_tokens 1, which only fails the check when
+/// the error body mentions images or vision: other 4xx errors (model not loaded, max_tokens
+/// rejected, bad model id) and connection problems are left for the caption request to report.
+/// The test-image result is not cached, since those errors are often temporary.
+pub async fn ensure_vision_model(base_url: &str, model: Option<&str>) -> Result<(), String> {
+    let base_url = base_url.trim_end_matches('/');
+    let key = format!("{}|{}", base_url, model.unwrap_or(""));
+    if let Some(cached) = VISION_PROBE_CACHE.lock().ok().and_then(|c| c.get(&key).cloned()) {
+        return cached;
+    }
+
+    let not_vision = |detail: &str| {
+        format!(
+            "Selected model {}does not accept images{}. Choose a vision model.",
+            model.map(|m| format!("\"{}\" ", m)).unwrap_or_default(),
+            detail
+        )
+    };
+    let client = reqwest::Client::new();
+    let timeout = std::time::Duration::from_secs(10);
+
+    #[derive(Deserialize)]
+    struct LmStudioModel {
+        #[serde(default, rename = "type")]
+        model_type: Option<String>,
+    }
+
+    if let Some(model) = model {
+        let url = format!("{}/api/v0/models/{}", base_url, model);
+        if let Ok(response) = client.get(&url).timeout(timeout).send().await {
+            if response.status().is_success() {
+                if let Ok(info) = response.json::<LmStudioModel>().await {
+                    let verdict = match info.model_type.as_deref() {
+                        Some("vlm") => Some(Ok(())),
+                        Some("llm") | Some("embeddings") => Some(Err(not_vision(""))),
+                        _ => None,
+                    };
+                    if let Some(verdict) = verdict {
+                        if let Ok(mut cache) = VISION_PROBE_CACHE.lock() {
+                            cache.insert(key, verdict.clone());
+                        }
+                        return verdict;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut buf = Vec::new();
+    image::DynamicImage::new_rgb8(8, 8)
+        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
+        .map_err(|e| e.to_string())?;
+    let body = serde_json::json!({
+        "model": model.unwrap_or("default"),
+        "messages": [{
+            "role": "user",
+            "content": [
+                { "type": "text", "text": "." },
+                {
+                    "type": "image_url",
+                    "image_url": { "url": format!("data:image/jpeg;base64,{}", BASE64.encode(&buf)) }
+                }
+            ]
+        }],
+        "max_tokens": 1
+    });
+    let url = format!("{}/v1/chat/completions", base_url);
+    let Ok(response) = client.post(&url).json(&body).timeout(timeout * 3).send().await else {
+        return Ok(());
+    };
+    if !response.status().is_client_error() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    let lower = body.to_lowercase();
+    if lower.contains("image") || lower.contains("vision") {
+        let detail = body.trim().chars().take(200).collect::<String>();
+        return Err(not_vision(&format!(" (server said: {})", detail)));
+    }
+    Ok(())
+}
+
 /// Generate a caption for a single image using LM Studio vision model.
+/// With stream: true, emits caption-token events while generating and still returns the full caption.
 #[tauri::command]
 pub async fn generate_caption_lm_studio(
+    app: AppHandle,
+    limiter: State<'_, AiConcurrencyLimiter>,
+    payload: GenerateCaptionPayload,
+) -> Result<CaptionResult, String> {
+    if let Err(e) = ensure_vision_model(&payload.base_url, payload.model.as_deref()).await {
+        return Ok(CaptionResult {
+            success: false,
+            caption: String::new(),
+            error: Some(e),
+        });
+    }
+    caption_image(payload, Some(&app), &limiter).await
+}
+
+/// Shared single-image request. Streaming needs an AppHandle to emit tokens; without one the
+/// request is never streamed.
+async fn caption_image(
     payload: GenerateCaptionPayload,
+    app: Option<&AppHandle>,
+    limiter: &AiConcurrencyLimiter,
 ) -> Result<CaptionResult, String> {
+    let stream_app = app.filter(|_| payload.stream);
     let path = PathBuf::from(&payload.image_path);
     if !path.exists() || !path.is_file() {
         return Ok(CaptionResult {
@@ -131,57 +477,74 @@ pub async fn generate_caption_lm_studio(
         });
     }
 
-    // Decode image so we can normalize to JPEG (LM Studio vision often only accepts JPEG).
-    // Optionally resize to reduce payload and inference time.
-    let img = image::open(&path).map_err(|e| e.to_string())?;
-    let (w, h) = (img.width(), img.height());
+    let base64_image = encode_image_jpeg_base64(&path, payload.max_image_dimension)?;
+    let data_url = format!("data:image/jpeg;base64,{}", base64_image);
 
-    let img = if let Some(max_dim) = payload.max_image_dimension.filter(|&d| d > 0) {
-        let longest = w.max(h);
-        if longest > max_dim {
-            let scale = max_dim as f32 / longest as f32;
-            let new_w = (w as f32 * scale).round() as u32;
-            let new_h = (h as f32 * scale).round() as u32;
-            let new_w = new_w.max(1);
-            let new_h = new_h.max(1);
-            img.resize(new_w, new_h, FilterType::Triangle)
-        } else {
-            img
+    let context_paths = payload.context_image_paths.as_deref().unwrap_or_default();
+    let mut context_urls = Vec::with_capacity(context_paths.len());
+    for context_path in context_paths {
+        let context_path = Path::new(context_path);
+        if !context_path.is_file() {
+            return Ok(CaptionResult {
+                success: false,
+                caption: String::new(),
+                error: Some(format!("Context image not found: {}", context_path.display())),
+            });
         }
-    } else {
-        img
-    };
-
-    let mut buf = Vec::new();
-    img.write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
-        .map_err(|e| e.to_string())?;
-    let base64_image = BASE64.encode(&buf);
-    let data_url = format!("data:image/jpeg;base64,{}", base64_image);
+        let encoded = encode_image_jpeg_base64(context_path, payload.max_image_dimension)?;
+        context_urls.push(format!("data:image/jpeg;base64,{}", encoded));
+    }
 
     // Build request body (OpenAI-compatible format)
-    let request_body = serde_json::json!({
-        "model": payload.model.unwrap_or_else(|| "default".to_string()),
-        "messages": [
-            {
-                "role": "user",
-                "content": [
-                    {
-                        "type": "text",
-                        "text": payload.prompt
-                    },
-                    {
-                        "type": "image_url",
-                        "image_url": {
-                            "url": data_url
-                        }
-                    }
-                ]
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = payload.system_prompt.as_deref().filter(|s| !s.trim().is_empty()) {
+        messages.push(serde_json::json!({
+            "role": "system",
+            "content": system_prompt
+        }));
+    }
+    // Context images come first and the target last; the prompt says which is which.
+    let prompt = match context_urls.len() {
+        0 => payload.prompt.clone(),
+        1 => format!(
+            "The first image is a reference example. Caption only the last image.\n\n{}",
+            payload.prompt
+        ),
+        n => format!(
+            "The first {} images are reference examples. Caption only the last image.\n\n{}",
+            n, payload.prompt
+        ),
+    };
+    let mut content = vec![serde_json::json!({
+        "type": "text",
+        "text": prompt
+    })];
+    for url in context_urls.iter().chain(std::iter::once(&data_url)) {
+        content.push(serde_json::json!({
+            "type": "image_url",
+            "image_url": {
+                "url": url
             }
-        ],
+        }));
+    }
+    messages.push(serde_json::json!({
+        "role": "user",
+        "content": content
+    }));
+
+    let mut request_body = serde_json::json!({
+        "model": payload.model.unwrap_or_else(|| "default".to_string()),
+        "messages": messages,
         "max_tokens": payload.max_tokens,
-        "temperature": 0.7,
-        "stream": false
+        "temperature": payload.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+        "stream": stream_app.is_some()
     });
+    if let Some(top_p) = payload.top_p {
+        request_body["top_p"] = serde_json::json!(top_p);
+    }
+    if let Some(stop) = payload.stop.as_ref().filter(|s| !s.is_empty()) {
+        request_body["stop"] = serde_json::json!(stop);
+    }
 
     let url = format!(
         "{}/v1/chat/completions",
@@ -189,6 +552,8 @@ pub async fn generate_caption_lm_studio(
     );
 
     let timeout_secs = payload.timeout_secs.min(MAX_TIMEOUT_SECS).max(1);
+    // Held until the caption (including a streamed one) has been read.
+    let _permit = limiter.acquire().await?;
     let client = reqwest::Client::new();
     let do_request = || {
         client
@@ -199,32 +564,14 @@ pub async fn generate_caption_lm_studio(
             .send()
     };
 
-    let response = match do_request().await {
+    let response = match send_with_timeout_retry(do_request, timeout_secs).await {
         Ok(r) => r,
         Err(e) => {
-            let err_str = e.to_string();
-            let is_timeout = err_str.contains("timed out") || err_str.contains("timeout");
-            if !is_timeout {
-                return Ok(CaptionResult {
-                    success: false,
-                    caption: String::new(),
-                    error: Some(format!("Request failed: {}", e)),
-                });
-            }
-            // Retry once on timeout
-            match do_request().await {
-                Ok(r) => r,
-                Err(_) => {
-                    return Ok(CaptionResult {
-                        success: false,
-                        caption: String::new(),
-                        error: Some(format!(
-                            "Request timed out after {} seconds (tried 2 times). Try a larger timeout in settings or use smaller images.",
-                            timeout_secs
-                        )),
-                    });
-                }
-            }
+            return Ok(CaptionResult {
+                success: false,
+                caption: String::new(),
+                error: Some(e),
+            });
         }
     };
 
@@ -238,6 +585,25 @@ pub async fn generate_caption_lm_studio(
         });
     }
 
+    if let Some(app) = stream_app {
+        return Ok(match read_streamed_caption(response, app, &payload.image_path).await {
+            Ok(caption) => CaptionResult {
+                success: true,
+                caption: clean_caption(
+                    &caption,
+                    payload.strip_prefixes.as_deref().unwrap_or_default(),
+                    payload.strip_default_boilerplate,
+                ),
+                error: None,
+            },
+            Err(e) => CaptionResult {
+                success: false,
+                caption: String::new(),
+                error: Some(e),
+            },
+        });
+    }
+
     #[derive(Deserialize)]
     struct ChatResponse {
         choices: Vec<Choice>,
@@ -272,12 +638,16 @@ pub async fn generate_caption_lm_studio(
 
     Ok(CaptionResult {
         success: true,
-        caption,
+        caption: clean_caption(
+            &caption,
+            payload.strip_prefixes.as_deref().unwrap_or_default(),
+            payload.strip_default_boilerplate,
+        ),
         error: None,
     })
 }
 
-fn default_batch_concurrency() -> u32 {
+pub fn default_batch_concurrency() -> u32 {
     1
 }
 
@@ -300,6 +670,104 @@ pub struct BatchCaptionPayload {
     /// Max concurrent requests (1 = sequential, 2–3 recommended).
     #[serde(default = "default_batch_concurrency")]
     pub concurrency: u32,
+    /// Identifier for cancel_caption_batch. Empty means the batch cannot be canceled.
+    #[serde(default)]
+    pub batch_id: String,
+    /// Optional system message sent before the user prompt.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Sampling temperature (default 0.7).
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Stop sequences passed through to the model.
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+    /// Extra leading phrases to strip from the caption (case-insensitive).
+    #[serde(default)]
+    pub strip_prefixes: Option<Vec<String>>,
+    /// Strip common openers like "This image shows" / "The image depicts".
+    #[serde(default)]
+    pub strip_default_boilerplate: bool,
+    /// Don't caption images that already have a non-empty caption file; they are reported as skipped.
+    #[serde(default)]
+    pub skip_existing: bool,
+    /// Write each successful caption to its caption file as soon as it is generated.
+    #[serde(default)]
+    pub write: bool,
+    /// How written captions combine with an existing caption (default replace).
+    #[serde(default)]
+    pub write_mode: CaptionMergeMode,
+    /// Trigger word placed first in every written caption.
+    #[serde(default)]
+    pub write_trigger_word: Option<String>,
+}
+
+/// What to do with each successful caption in a batch that writes captions itself.
+#[derive(Debug, Clone)]
+pub struct BatchWriteOptions {
+    pub mode: CaptionMergeMode,
+    pub trigger_word: Option<String>,
+}
+
+impl BatchWriteOptions {
+    pub fn new(write: bool, mode: CaptionMergeMode, trigger_word: Option<&String>) -> Option<Self> {
+        write.then(|| Self {
+            mode,
+            trigger_word: trigger_word
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty()),
+        })
+    }
+}
+
+/// Write a generated caption (comma-separated tags) for image_path. A failed write turns the
+/// result into a failure that keeps the caption, so nothing is lost on the frontend side.
+pub fn write_batch_caption(
+    image_path: &str,
+    result: Result<CaptionResult, String>,
+    write: Option<&BatchWriteOptions>,
+    project: &ProjectLookup,
+) -> Result<CaptionResult, String> {
+    let (Some(opts), Ok(r)) = (write, &result) else {
+        return result;
+    };
+    if !r.success || r.caption.trim().is_empty() {
+        return result;
+    }
+    let tags = r.caption.split(',').map(|t| t.to_string()).collect();
+    match merge_caption_file(image_path, tags, opts.mode, opts.trigger_word.as_deref(), project) {
+        Ok(_) => result,
+        Err(e) => Ok(CaptionResult {
+            success: false,
+            caption: r.caption.clone(),
+            error: Some(format!("Caption generated but not saved: {}", e)),
+        }),
+    }
+}
+
+/// Substitute per-image tokens in a batch prompt: {filename}, {folder} (parent folder name) and
+/// {existing_tags} (the image's current caption, empty if it has none).
+pub fn render_prompt_template(prompt: &str, image_path: &str, project: &ProjectLookup) -> String {
+    if !prompt.contains('{') {
+        return prompt.to_string();
+    }
+    let path = Path::new(image_path);
+    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let folder = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .unwrap_or("");
+    let mut rendered = prompt
+        .replace("{filename}", filename)
+        .replace("{folder}", folder);
+    if rendered.contains("{existing_tags}") {
+        let existing = read_caption_text(project.caption_path(path)).unwrap_or_default();
+        rendered = rendered.replace("{existing_tags}", existing.trim());
+    }
+    rendered
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -308,15 +776,160 @@ pub struct BatchCaptionResult {
     pub success: bool,
     pub caption: String,
     pub error: Option<String>,
+    /// Not captioned because it already had a caption (skip_existing); caption is empty.
+    pub skipped: bool,
+    /// Caption file the batch wrote this caption to (when the batch was asked to write).
+    pub written_path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchCaptionResponse {
+    /// Results in image_paths order; images skipped after cancellation are omitted.
+    pub results: Vec<BatchCaptionResult>,
+    pub canceled: bool,
+    /// Images left alone because they already had a caption.
+    pub skipped_count: usize,
+}
+
+/// Register a cancellation flag for batch_id (a fresh, unset flag if the id is reused).
+pub fn register_caption_batch(batch_id: &str) -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    if !batch_id.is_empty() {
+        if let Ok(mut flags) = BATCH_CANCEL_FLAGS.lock() {
+            flags.insert(batch_id.to_string(), flag.clone());
+        }
+    }
+    flag
+}
+
+pub fn unregister_caption_batch(batch_id: &str) {
+    if let Ok(mut flags) = BATCH_CANCEL_FLAGS.lock() {
+        flags.remove(batch_id);
+    }
+}
+
+/// One finished batch entry: (input index, image path, caption outcome).
+pub type BatchEntry = (usize, String, Result<CaptionResult, String>);
+
+/// Batch image paths paired with their input index.
+pub type IndexedPaths = Vec<(usize, String)>;
+
+/// Whether the image already has a caption file with any non-whitespace content.
+fn has_existing_caption(image_path: &str, project: &ProjectLookup) -> bool {
+    read_caption_text(project.caption_path(Path::new(image_path)))
+        .is_ok_and(|c| !c.trim().is_empty())
+}
+
+/// Index the batch's images and split off those to skip when skip_existing is set.
+/// Returns (to caption, skipped).
+pub fn partition_batch_images(
+    image_paths: Vec<String>,
+    skip_existing: bool,
+    project: &ProjectLookup,
+) -> (IndexedPaths, IndexedPaths) {
+    image_paths
+        .into_iter()
+        .enumerate()
+        .partition(|(_, path)| !(skip_existing && has_existing_caption(path, project)))
+}
+
+/// Sort completed and skipped batch entries back into input order. Entries skipped after
+/// cancellation are None.
+pub fn collect_batch_results(
+    completed: Vec<Option<BatchEntry>>,
+    skipped: IndexedPaths,
+    canceled: bool,
+    wrote_captions: bool,
+    project: &ProjectLookup,
+) -> BatchCaptionResponse {
+    let skipped_count = skipped.len();
+    let mut results: Vec<(usize, BatchCaptionResult)> = completed
+        .into_iter()
+        .flatten()
+        .map(|(i, path, result)| {
+            let result = match result {
+                Ok(r) => {
+                    let written = wrote_captions && r.success && !r.caption.trim().is_empty();
+                    let written_path = written.then(|| {
+                        project
+                            .caption_path(Path::new(&path))
+                            .to_string_lossy()
+                            .into_owned()
+                    });
+                    BatchCaptionResult {
+                        path,
+                        success: r.success,
+                        caption: r.caption,
+                        error: r.error,
+                        skipped: false,
+                        written_path,
+                    }
+                }
+                Err(e) => BatchCaptionResult {
+                    path,
+                    success: false,
+                    caption: String::new(),
+                    error: Some(e),
+                    skipped: false,
+                    written_path: None,
+                },
+            };
+            (i, result)
+        })
+        .chain(skipped.into_iter().map(|(i, path)| {
+            let result = BatchCaptionResult {
+                path,
+                success: true,
+                caption: String::new(),
+                error: None,
+                skipped: true,
+                written_path: None,
+            };
+            (i, result)
+        }))
+        .collect();
+    results.sort_by_key(|(i, _)| *i);
+
+    BatchCaptionResponse {
+        results: results.into_iter().map(|(_, r)| r).collect(),
+        canceled,
+        skipped_count,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelCaptionBatchPayload {
+    pub batch_id: String,
+}
+
+/// Stop a running caption batch. Requests already in flight finish; no new ones are started.
+/// Returns false if no batch with that id is running.
+#[tauri::command]
+pub fn cancel_caption_batch(payload: CancelCaptionBatchPayload) -> Result<bool, String> {
+    let flags = BATCH_CANCEL_FLAGS.lock().map_err(|e| e.to_string())?;
+    match flags.get(&payload.batch_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
 }
 
-/// Generate captions for multiple images with bounded concurrency.
-/// Results are returned in the same order as image_paths.
+/// Generate captions for multiple images with bounded concurrency. The prompt may use the
+/// per-image tokens of render_prompt_template.
+/// Results are returned in the same order as image_paths. If the batch is canceled,
+/// only the results collected so far are returned and canceled is set.
+/// Fails up front if the selected model does not accept images.
 #[tauri::command]
 pub async fn generate_captions_batch(
+    limiter: State<'_, AiConcurrencyLimiter>,
     payload: BatchCaptionPayload,
-) -> Result<Vec<BatchCaptionResult>, String> {
-    let concurrency = payload.concurrency.max(1).min(8) as usize;
+) -> Result<BatchCaptionResponse, String> {
+    let limiter = limiter.inner();
+    ensure_vision_model(&payload.base_url, payload.model.as_deref()).await?;
+    let concurrency = payload.concurrency.clamp(1, 8) as usize;
+    let cancel_flag = register_caption_batch(&payload.batch_id);
 
     let base_url = payload.base_url.clone();
     let model = payload.model.clone();
@@ -324,15 +937,30 @@ pub async fn generate_captions_batch(
     let max_tokens = payload.max_tokens;
     let timeout_secs = payload.timeout_secs;
     let max_image_dimension = payload.max_image_dimension;
+    let system_prompt = payload.system_prompt.clone();
+    let temperature = payload.temperature;
+    let top_p = payload.top_p;
+    let stop = payload.stop.clone();
+    let strip_prefixes = payload.strip_prefixes.clone();
+    let strip_default_boilerplate = payload.strip_default_boilerplate;
+    // Caption extensions and backup settings, resolved once per folder for the whole batch.
+    let project = &ProjectLookup::default();
+    let (to_caption, skipped) =
+        partition_batch_images(payload.image_paths, payload.skip_existing, project);
+    let write = BatchWriteOptions::new(
+        payload.write,
+        payload.write_mode,
+        payload.write_trigger_word.as_ref(),
+    );
 
-    let futures = payload
-        .image_paths
+    let futures = to_caption
         .into_iter()
-        .enumerate()
         .map(|(index, path)| {
             let base_url = base_url.clone();
             let model = model.clone();
-            let prompt = prompt.clone();
+            let prompt = render_prompt_template(&prompt, &path, project);
+            let cancel_flag = cancel_flag.clone();
+            let write = write.clone();
             let single_payload = GenerateCaptionPayload {
                 image_path: path.clone(),
                 base_url,
@@ -341,39 +969,36 @@ pub async fn generate_captions_batch(
                 max_tokens,
                 timeout_secs,
                 max_image_dimension,
+                stream: false,
+                system_prompt: system_prompt.clone(),
+                temperature,
+                top_p,
+                stop: stop.clone(),
+                strip_prefixes: strip_prefixes.clone(),
+                strip_default_boilerplate,
+                context_image_paths: None,
             };
             async move {
-                let result = generate_caption_lm_studio(single_payload).await;
-                (index, path, result)
+                if cancel_flag.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let result = caption_image(single_payload, None, limiter).await;
+                let result = write_batch_caption(&path, result, write.as_ref(), project);
+                Some((index, path, result))
             }
         });
 
-    let mut completed: Vec<(usize, String, Result<CaptionResult, String>)> = stream::iter(futures)
+    let completed: Vec<_> = stream::iter(futures)
         .buffer_unordered(concurrency)
         .collect()
         .await;
 
-    completed.sort_by_key(|(i, _, _)| *i);
-
-    let results: Vec<BatchCaptionResult> = completed
-        .into_iter()
-        .map(|(_, path, result)| {
-            match result {
-                Ok(r) => BatchCaptionResult {
-                    path,
-                    success: r.success,
-                    caption: r.caption,
-                    error: r.error,
-                },
-                Err(e) => BatchCaptionResult {
-                    path,
-                    success: false,
-                    caption: String::new(),
-                    error: Some(e),
-                },
-            }
-        })
-        .collect();
-
-    Ok(results)
+    unregister_caption_batch(&payload.batch_id);
+    Ok(collect_batch_results(
+        completed,
+        skipped,
+        cancel_flag.load(Ordering::Relaxed),
+        payload.write,
+        project,
+    ))
 }
@@ -0,0 +1,114 @@
+//! Writes for the JSON files under `.lora-studio` (ratings, crop status, rename history).
+//! Every write goes to a temp file that is renamed into place, so a crash mid-write never leaves
+//! a truncated file. Frequent single-image edits use `write_debounced`, which keeps the latest
+//! content in memory and writes it once after a short quiet period; `read_to_string` sees those
+//! pending contents so loads stay consistent. A failed background write keeps the content pending
+//! and makes the next `write_debounced` for that file write synchronously and return the error.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// Bursts of edits within this window are coalesced into one write per file.
+const WRITE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Default)]
+struct PendingWrites {
+    contents: HashMap<PathBuf, String>,
+    flusher_running: bool,
+    /// Files whose last background write failed, with the error.
+    failed: HashMap<PathBuf, String>,
+}
+
+/// Held while writing too, so a flush can never rename stale content over a newer immediate write.
+static PENDING: Lazy<Mutex<PendingWrites>> = Lazy::new(|| Mutex::new(PendingWrites::default()));
+
+/// Write `content` to a sibling temp file, then rename it over `path`.
+fn write_file_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let parent = path.parent().ok_or("No parent directory")?;
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid file name")?;
+    let tmp = parent.join(format!(".{}.tmp", file_name));
+    fs::write(&tmp, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })
+}
+
+/// Write immediately (atomically), replacing any pending debounced content for the same file.
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let mut pending = PENDING.lock().unwrap();
+    pending.contents.remove(path);
+    pending.failed.remove(path);
+    write_file_atomic(path, content)
+}
+
+/// Queue a write; the latest content per file is written once the debounce window passes.
+/// If the previous background write of this file failed, this one is written right away so the
+/// caller learns whether saving works; on failure the content stays pending and is retried.
+pub fn write_debounced(path: &Path, content: String) -> Result<(), String> {
+    let mut pending = PENDING.lock().unwrap();
+    if let Some(previous) = pending.failed.remove(path) {
+        if let Err(e) = write_file_atomic(path, &content) {
+            pending.contents.insert(path.to_path_buf(), content);
+            pending.failed.insert(path.to_path_buf(), e.clone());
+            return Err(format!(
+                "Could not save {}: {} (earlier save failed: {}). Changes are kept and retried.",
+                path.display(),
+                e,
+                previous
+            ));
+        }
+        pending.contents.remove(path);
+        return Ok(());
+    }
+    pending.contents.insert(path.to_path_buf(), content);
+    if !pending.flusher_running {
+        pending.flusher_running = true;
+        thread::spawn(|| {
+            thread::sleep(WRITE_DEBOUNCE);
+            let mut pending = PENDING.lock().unwrap();
+            pending.flusher_running = false;
+            flush_locked(&mut pending);
+        });
+    }
+    Ok(())
+}
+
+fn flush_locked(pending: &mut PendingWrites) {
+    for (path, content) in std::mem::take(&mut pending.contents) {
+        match write_file_atomic(&path, &content) {
+            Ok(()) => {
+                pending.failed.remove(&path);
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to write {}: {}", path.display(), e);
+                // Keep it so reads still see it and the next write for this file reports the error.
+                pending.contents.insert(path.clone(), content);
+                pending.failed.insert(path, e);
+            }
+        }
+    }
+}
+
+/// Write out everything still pending (called on exit).
+pub fn flush_pending_writes() {
+    flush_locked(&mut PENDING.lock().unwrap());
+}
+
+/// Read a metadata file, preferring content that is still waiting to be written.
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    if let Some(content) = PENDING.lock().unwrap().contents.get(path) {
+        return Ok(content.clone());
+    }
+    fs::read_to_string(path)
+}
@@ -1,10 +1,18 @@
+pub mod ai_concurrency;
 pub mod batch_rename;
+pub mod caption_backups;
 pub mod captions;
 pub mod crop_status;
 pub mod detect;
+pub mod disk;
+pub mod error;
 pub mod export;
 pub mod images;
 pub mod lm_studio;
+pub mod metadata_store;
 pub mod ollama;
 pub mod project;
+pub mod project_config;
 pub mod ratings;
+pub mod scan_filter;
+pub mod watch;
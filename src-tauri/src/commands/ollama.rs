@@ -1,8 +1,23 @@
 //! Ollama provider: OpenAI-compatible API at http://localhost:11434/v1.
-//! Listing models uses GET /api/tags (base URL without /v1).
-//! Generation reuses lm_studio commands with Ollama base URL and model.
+//! Listing models uses GET /api/tags and captioning uses POST /api/generate (base URL without /v1).
+//! Image normalization and timeout/retry are shared with the LM Studio commands.
 
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use tauri::State;
+
+use super::ai_concurrency::AiConcurrencyLimiter;
+use super::captions::CaptionMergeMode;
+use super::lm_studio::{
+    collect_batch_results, default_batch_concurrency, default_max_tokens, default_timeout_secs,
+    encode_image_jpeg_base64, partition_batch_images, register_caption_batch,
+    render_prompt_template, send_with_timeout_retry, unregister_caption_batch,
+    write_batch_caption, BatchCaptionResponse, BatchWriteOptions, CaptionResult,
+    MAX_TIMEOUT_SECS,
+};
+use super::project_config::ProjectLookup;
 
 const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434/v1";
 
@@ -33,6 +48,15 @@ struct OllamaModelInfo {
     name: String,
 }
 
+/// Native API host for an OpenAI-compatible base_url (strips a trailing /v1).
+fn ollama_host(base_url: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    base.strip_suffix("/v1")
+        .unwrap_or(base)
+        .trim_end_matches('/')
+        .to_string()
+}
+
 /// Test connection to Ollama and list available models (including vision models like llava).
 /// base_url should be the OpenAI-compatible endpoint (e.g. http://localhost:11434/v1).
 /// We call /api/tags on the host (base_url with /v1 stripped).
@@ -40,12 +64,7 @@ struct OllamaModelInfo {
 pub async fn test_ollama_connection(
     payload: TestOllamaConnectionPayload,
 ) -> Result<ConnectionStatus, String> {
-    let base = payload.base_url.trim_end_matches('/');
-    let tags_url = if base.ends_with("/v1") {
-        format!("{}/api/tags", base.trim_end_matches("/v1").trim_end_matches('/'))
-    } else {
-        format!("{}/api/tags", base)
-    };
+    let tags_url = format!("{}/api/tags", ollama_host(&payload.base_url));
 
     let client = reqwest::Client::new();
     let response = match client
@@ -86,3 +105,203 @@ pub async fn test_ollama_connection(
         error: None,
     })
 }
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateOllamaCaptionPayload {
+    pub image_path: String,
+    #[serde(default = "default_ollama_base_url")]
+    pub base_url: String,
+    /// Model name from test_ollama_connection (e.g. "llava:13b"). Required by Ollama.
+    #[serde(default)]
+    pub model: Option<String>,
+    pub prompt: String,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+    /// Request timeout in seconds (default 120, max 600).
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u32,
+    /// If set, resize image so longest side is at most this (reduces payload and inference time).
+    #[serde(default)]
+    pub max_image_dimension: Option<u32>,
+}
+
+fn caption_error(error: String) -> CaptionResult {
+    CaptionResult {
+        success: false,
+        caption: String::new(),
+        error: Some(error),
+    }
+}
+
+/// Generate a caption for a single image with an Ollama vision model via /api/generate.
+#[tauri::command]
+pub async fn generate_caption_ollama(
+    limiter: State<'_, AiConcurrencyLimiter>,
+    payload: GenerateOllamaCaptionPayload,
+) -> Result<CaptionResult, String> {
+    caption_image(payload, &limiter).await
+}
+
+/// Shared by the single and batch commands.
+async fn caption_image(
+    payload: GenerateOllamaCaptionPayload,
+    limiter: &AiConcurrencyLimiter,
+) -> Result<CaptionResult, String> {
+    let path = PathBuf::from(&payload.image_path);
+    if !path.exists() || !path.is_file() {
+        return Ok(caption_error("Image file not found".to_string()));
+    }
+    let model = match payload.model.as_deref().map(str::trim) {
+        Some(m) if !m.is_empty() => m.to_string(),
+        _ => return Ok(caption_error("No Ollama model selected".to_string())),
+    };
+
+    let base64_image = encode_image_jpeg_base64(&path, payload.max_image_dimension)?;
+
+    let request_body = serde_json::json!({
+        "model": model,
+        "prompt": payload.prompt,
+        "images": [base64_image],
+        "stream": false,
+        "options": {
+            "num_predict": payload.max_tokens,
+            "temperature": 0.7
+        }
+    });
+
+    let url = format!("{}/api/generate", ollama_host(&payload.base_url));
+
+    let timeout_secs = payload.timeout_secs.clamp(1, MAX_TIMEOUT_SECS);
+    let _permit = limiter.acquire().await?;
+    let client = reqwest::Client::new();
+    let do_request = || {
+        client
+            .post(&url)
+            .json(&request_body)
+            .timeout(std::time::Duration::from_secs(timeout_secs as u64))
+            .send()
+    };
+
+    let response = match send_with_timeout_retry(do_request, timeout_secs).await {
+        Ok(r) => r,
+        Err(e) => return Ok(caption_error(e)),
+    };
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Ok(caption_error(format!("Ollama error {}: {}", status, body)));
+    }
+
+    #[derive(Deserialize)]
+    struct GenerateResponse {
+        response: String,
+    }
+
+    let generated: GenerateResponse = match response.json().await {
+        Ok(r) => r,
+        Err(e) => return Ok(caption_error(format!("Failed to parse response: {}", e))),
+    };
+
+    Ok(CaptionResult {
+        success: true,
+        caption: generated.response.trim().to_string(),
+        error: None,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaBatchCaptionPayload {
+    pub image_paths: Vec<String>,
+    #[serde(default = "default_ollama_base_url")]
+    pub base_url: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub prompt: String,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+    /// Request timeout in seconds per image (default 120, max 600).
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u32,
+    /// If set, resize each image so longest side is at most this.
+    #[serde(default)]
+    pub max_image_dimension: Option<u32>,
+    /// Max concurrent requests (1 = sequential).
+    #[serde(default = "default_batch_concurrency")]
+    pub concurrency: u32,
+    /// Identifier for cancel_caption_batch. Empty means the batch cannot be canceled.
+    #[serde(default)]
+    pub batch_id: String,
+    /// Don't caption images that already have a non-empty caption file; they are reported as skipped.
+    #[serde(default)]
+    pub skip_existing: bool,
+    /// Write each successful caption to its caption file as soon as it is generated.
+    #[serde(default)]
+    pub write: bool,
+    /// How written captions combine with an existing caption (default replace).
+    #[serde(default)]
+    pub write_mode: CaptionMergeMode,
+    /// Trigger word placed first in every written caption.
+    #[serde(default)]
+    pub write_trigger_word: Option<String>,
+}
+
+/// Generate captions for multiple images with Ollama using bounded concurrency.
+/// The prompt may use the per-image tokens of render_prompt_template.
+/// Results are returned in the same order as image_paths; cancelable via cancel_caption_batch.
+#[tauri::command]
+pub async fn generate_captions_ollama_batch(
+    limiter: State<'_, AiConcurrencyLimiter>,
+    payload: OllamaBatchCaptionPayload,
+) -> Result<BatchCaptionResponse, String> {
+    let limiter = limiter.inner();
+    let concurrency = payload.concurrency.clamp(1, 8) as usize;
+    let cancel_flag = register_caption_batch(&payload.batch_id);
+    // Caption extensions and backup settings, resolved once per folder for the whole batch.
+    let project = &ProjectLookup::default();
+    let (to_caption, skipped) =
+        partition_batch_images(payload.image_paths.clone(), payload.skip_existing, project);
+    let write = BatchWriteOptions::new(
+        payload.write,
+        payload.write_mode,
+        payload.write_trigger_word.as_ref(),
+    );
+
+    let futures = to_caption
+        .into_iter()
+        .map(|(index, path)| {
+            let cancel_flag = cancel_flag.clone();
+            let write = write.clone();
+            let single_payload = GenerateOllamaCaptionPayload {
+                image_path: path.clone(),
+                base_url: payload.base_url.clone(),
+                model: payload.model.clone(),
+                prompt: render_prompt_template(&payload.prompt, &path, project),
+                max_tokens: payload.max_tokens,
+                timeout_secs: payload.timeout_secs,
+                max_image_dimension: payload.max_image_dimension,
+            };
+            async move {
+                if cancel_flag.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let result = caption_image(single_payload, limiter).await;
+                let result = write_batch_caption(&path, result, write.as_ref(), project);
+                Some((index, path, result))
+            }
+        });
+
+    let completed: Vec<_> = stream::iter(futures)
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    unregister_caption_batch(&payload.batch_id);
+    Ok(collect_batch_results(
+        completed,
+        skipped,
+        cancel_flag.load(Ordering::Relaxed),
+        payload.write,
+        project,
+    ))
+}
@@ -1,18 +1,27 @@
 use image::ImageReader;
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
 use tauri::{AppHandle, Emitter};
 use walkdir::WalkDir;
 
-use super::ratings::{load_ratings, ImageRating};
+use super::captions::read_caption_text;
+use super::project_config::{caption_extension, load_project_config, ProjectConfig};
+use super::ratings::{load_ratings, ImageRating, RatingsData};
+use super::scan_filter::ScanIgnore;
 
 const PROGRESS_EVENT: &str = "project-load-progress";
+const FIND_DUPLICATES_PROGRESS_EVENT: &str = "find-duplicates-progress";
+const NEAR_DUPLICATES_PROGRESS_EVENT: &str = "near-duplicates-progress";
+const DETECT_GRAYSCALE_PROGRESS_EVENT: &str = "detect-grayscale-progress";
+const DETECT_BLURRY_PROGRESS_EVENT: &str = "detect-blurry-progress";
 
 const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
 
@@ -26,9 +35,25 @@ fn is_image_path(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
-/// Get the caption file path for an image (same name, .txt extension).
-fn caption_path_for(image_path: &Path) -> PathBuf {
-    image_path.with_extension("txt")
+/// All image files under root (or directly in it when not recursive), skipping ignored folders.
+fn collect_image_paths(root: &Path, recursive: bool, ignore: &ScanIgnore) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .max_depth(scan_depth(recursive))
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(|e| e.ok())
+        .filter(|entry| {
+            let path = entry.path();
+            path.is_file() && is_image_path(path)
+        })
+        .map(|entry| entry.path().to_path_buf())
+        .collect()
+}
+
+/// Get the caption file path for an image (same name, project caption extension).
+fn caption_path_for(image_path: &Path, caption_ext: &str) -> PathBuf {
+    image_path.with_extension(caption_ext)
 }
 
 /// Parse comma-separated tags from raw caption text.
@@ -44,12 +69,32 @@ pub struct OpenProjectPayload {
     pub root_path: String,
     #[serde(default = "default_false")]
     pub include_dimensions: bool,
+    /// Include images in subfolders (default). When false, only images directly in root_path.
+    #[serde(default = "default_true")]
+    pub recursive: bool,
+    /// Folder-name patterns to skip (see ScanIgnore); None skips hidden, originals and
+    /// caption_backups folders. `.lora-studio` is always skipped.
+    #[serde(default)]
+    pub ignore_patterns: Option<Vec<String>>,
 }
 
 fn default_false() -> bool {
     false
 }
 
+fn default_true() -> bool {
+    true
+}
+
+/// WalkDir max_depth for a project scan: unlimited, or only the root folder's own files.
+fn scan_depth(recursive: bool) -> usize {
+    if recursive {
+        usize::MAX
+    } else {
+        1
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ImageEntry {
     pub id: String,
@@ -65,17 +110,130 @@ pub struct ImageEntry {
     pub height: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_size: Option<u64>,
+    /// Image and caption mtimes (ms since epoch), passed back to rescan_project.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption_modified_ms: Option<u64>,
 }
 
+/// Entries from the last open_project/rescan_project, so filter_images doesn't rescan the folder.
+struct ProjectCache {
+    root: PathBuf,
+    caption_ext: String,
+    entries: Vec<ImageEntry>,
+}
+
+static PROJECT_CACHE: Lazy<Mutex<Option<ProjectCache>>> = Lazy::new(|| Mutex::new(None));
+
 #[derive(Debug, Clone, Serialize)]
 struct ProjectLoadProgress {
     count: usize,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct ScanProgress {
+    processed: usize,
+    total: usize,
+}
+
+/// Modification time in ms since the epoch.
+fn mtime_ms(metadata: &fs::Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_millis() as u64)
+}
+
+/// Build the entry for one image: caption tags, rating, and optionally header-only dimensions.
+fn build_image_entry(
+    path: &Path,
+    canonical_root: &Path,
+    caption_ext: &str,
+    ratings: &RatingsData,
+    include_dimensions: bool,
+) -> Result<ImageEntry, String> {
+    let path_buf = path.to_path_buf();
+    let path_str = path_buf
+        .to_str()
+        .ok_or("Invalid path encoding")?
+        .to_string();
+    let relative = path_buf
+        .strip_prefix(canonical_root)
+        .unwrap_or_else(|_| path_buf.as_path());
+    let relative_path = relative
+        .to_str()
+        .ok_or("Invalid path encoding")?
+        .replace('\\', "/");
+    let filename = path_buf
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_string();
+    let id = path_str.clone();
+
+    // Read caption file if exists
+    let caption_path = caption_path_for(&path_buf, caption_ext);
+    let (has_caption, tags) = if caption_path.exists() {
+        match read_caption_text(&caption_path) {
+            Ok(raw) => (true, parse_tags(&raw)),
+            Err(_) => (false, Vec::new()),
+        }
+    } else {
+        (false, Vec::new())
+    };
+
+    // Get rating from loaded ratings data
+    let rating = ratings
+        .ratings
+        .get(&relative_path)
+        .map(|s| ImageRating::from_str(s))
+        .unwrap_or(ImageRating::None);
+
+    // Read image dimensions (header only, fast) - optional for performance
+    let (width, height) = if include_dimensions {
+        ImageReader::open(&path_buf)
+            .ok()
+            .and_then(|r| r.into_dimensions().ok())
+            .unwrap_or((0u32, 0u32))
+    } else {
+        (0u32, 0u32)
+    };
+    let width = if width > 0 { Some(width) } else { None };
+    let height = if height > 0 { Some(height) } else { None };
+
+    let metadata = fs::metadata(&path_buf).ok();
+    let file_size = metadata.as_ref().map(|m| m.len()).filter(|&n| n > 0);
+    let modified_ms = metadata.as_ref().and_then(mtime_ms);
+    let caption_modified_ms = fs::metadata(&caption_path).ok().as_ref().and_then(mtime_ms);
+
+    Ok(ImageEntry {
+        id,
+        path: path_str,
+        relative_path,
+        filename,
+        has_caption,
+        tags,
+        rating: rating.as_str().to_string(),
+        width,
+        height,
+        file_size,
+        modified_ms,
+        caption_modified_ms,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenProjectResult {
+    pub entries: Vec<ImageEntry>,
+    /// The project's `.lora-studio/config.json` (defaults if it does not exist), so the UI can
+    /// restore per-project settings without a separate read_project_config call.
+    pub config: ProjectConfig,
+}
+
 /// Opens a project at the given root path. Scans recursively for image files.
 /// Emits progress events as images are discovered.
 #[tauri::command]
-pub fn open_project(app: AppHandle, payload: OpenProjectPayload) -> Result<Vec<ImageEntry>, String> {
+pub fn open_project(app: AppHandle, payload: OpenProjectPayload) -> Result<OpenProjectResult, String> {
     let root = PathBuf::from(&payload.root_path);
     if !root.exists() {
         return Err("Folder does not exist".to_string());
@@ -85,81 +243,29 @@ pub fn open_project(app: AppHandle, payload: OpenProjectPayload) -> Result<Vec<I
     }
 
     let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let caption_ext = caption_extension(&canonical_root);
     let ratings_data = load_ratings(&payload.root_path);
     let mut entries = Vec::new();
+    let ignore = ScanIgnore::new(payload.ignore_patterns.as_deref());
 
     for entry in WalkDir::new(&canonical_root)
+        .max_depth(scan_depth(payload.recursive))
         .follow_links(false)
         .into_iter()
+        .filter_entry(|e| ignore.allows(e))
         .filter_map(|e| e.ok())
     {
         let path = entry.path();
         if !path.is_file() || !is_image_path(path) {
             continue;
         }
-        let path_buf = path.to_path_buf();
-        let path_str = path_buf
-            .to_str()
-            .ok_or("Invalid path encoding")?
-            .to_string();
-        let relative = path_buf
-            .strip_prefix(&canonical_root)
-            .unwrap_or_else(|_| path_buf.as_path());
-        let relative_path = relative
-            .to_str()
-            .ok_or("Invalid path encoding")?
-            .replace('\\', "/");
-        let filename = path_buf
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("")
-            .to_string();
-        let id = path_str.clone();
-
-        // Read caption file if exists
-        let caption_path = caption_path_for(&path_buf);
-        let (has_caption, tags) = if caption_path.exists() {
-            match fs::read_to_string(&caption_path) {
-                Ok(raw) => (true, parse_tags(&raw)),
-                Err(_) => (false, Vec::new()),
-            }
-        } else {
-            (false, Vec::new())
-        };
-
-        // Get rating from loaded ratings data
-        let rating = ratings_data
-            .ratings
-            .get(&relative_path)
-            .map(|s| ImageRating::from_str(s))
-            .unwrap_or(ImageRating::None);
-
-        // Read image dimensions (header only, fast) - optional for performance
-        let (width, height) = if payload.include_dimensions {
-            ImageReader::open(&path_buf)
-                .ok()
-                .and_then(|r| r.into_dimensions().ok())
-                .unwrap_or((0u32, 0u32))
-        } else {
-            (0u32, 0u32)
-        };
-        let width = if width > 0 { Some(width) } else { None };
-        let height = if height > 0 { Some(height) } else { None };
-
-        let file_size = fs::metadata(&path_buf).ok().map(|m| m.len()).filter(|&n| n > 0);
-
-        entries.push(ImageEntry {
-            id,
-            path: path_str,
-            relative_path,
-            filename,
-            has_caption,
-            tags,
-            rating: rating.as_str().to_string(),
-            width,
-            height,
-            file_size,
-        });
+        entries.push(build_image_entry(
+            path,
+            &canonical_root,
+            &caption_ext,
+            &ratings_data,
+            payload.include_dimensions,
+        )?);
 
         // Emit progress every 25 images (more frequent for better UX)
         if entries.len() % 25 == 0 {
@@ -171,12 +277,530 @@ pub fn open_project(app: AppHandle, payload: OpenProjectPayload) -> Result<Vec<I
     let _ = app.emit(PROGRESS_EVENT, ProjectLoadProgress { count: entries.len() });
 
     entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
-    Ok(entries)
+    let config = load_project_config(&canonical_root);
+    *PROJECT_CACHE.lock().unwrap() = Some(ProjectCache {
+        root: canonical_root,
+        caption_ext,
+        entries: entries.clone(),
+    });
+    Ok(OpenProjectResult { entries, config })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KnownImage {
+    pub relative_path: String,
+    #[serde(default)]
+    pub modified_ms: Option<u64>,
+    #[serde(default)]
+    pub caption_modified_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RescanProjectPayload {
+    pub root_path: String,
+    #[serde(default = "default_false")]
+    pub include_dimensions: bool,
+    /// Entries from the previous open_project/rescan_project (only paths and mtimes are needed).
+    pub known: Vec<KnownImage>,
+    /// recursive and ignore_patterns must match the open_project call.
+    #[serde(default = "default_true")]
+    pub recursive: bool,
+    #[serde(default)]
+    pub ignore_patterns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RescanProjectResult {
+    pub added: Vec<ImageEntry>,
+    /// Relative paths of images that no longer exist.
+    pub removed: Vec<String>,
+    /// Images whose file or caption mtime changed (including captions added or deleted).
+    pub changed: Vec<ImageEntry>,
+}
+
+/// Refresh a project against previously returned entries: only images that are new or whose
+/// image/caption mtime changed are re-read.
+#[tauri::command]
+pub fn rescan_project(payload: RescanProjectPayload) -> Result<RescanProjectResult, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let caption_ext = caption_extension(&canonical_root);
+    let ratings_data = load_ratings(&payload.root_path);
+
+    let mut known: HashMap<&str, &KnownImage> = payload
+        .known
+        .iter()
+        .map(|k| (k.relative_path.as_str(), k))
+        .collect();
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    let ignore = ScanIgnore::new(payload.ignore_patterns.as_deref());
+
+    for entry in WalkDir::new(&canonical_root)
+        .max_depth(scan_depth(payload.recursive))
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if !path.is_file() || !is_image_path(path) {
+            continue;
+        }
+        let relative_path = relative_to(&canonical_root, path);
+        match known.remove(relative_path.as_str()) {
+            Some(prev) => {
+                let modified = fs::metadata(path).ok().as_ref().and_then(mtime_ms);
+                let caption_modified = fs::metadata(caption_path_for(path, &caption_ext))
+                    .ok()
+                    .as_ref()
+                    .and_then(mtime_ms);
+                if modified != prev.modified_ms || caption_modified != prev.caption_modified_ms {
+                    changed.push(build_image_entry(
+                        path,
+                        &canonical_root,
+                        &caption_ext,
+                        &ratings_data,
+                        payload.include_dimensions,
+                    )?);
+                }
+            }
+            None => added.push(build_image_entry(
+                path,
+                &canonical_root,
+                &caption_ext,
+                &ratings_data,
+                payload.include_dimensions,
+            )?),
+        }
+    }
+
+    let mut removed: Vec<String> = known.into_keys().map(str::to_string).collect();
+    removed.sort();
+    added.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    changed.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    if let Some(cache) = PROJECT_CACHE
+        .lock()
+        .unwrap()
+        .as_mut()
+        .filter(|c| c.root == canonical_root)
+    {
+        let removed_set: HashSet<&str> = removed.iter().map(String::as_str).collect();
+        let changed_by_path: HashMap<&str, &ImageEntry> = changed
+            .iter()
+            .map(|e| (e.relative_path.as_str(), e))
+            .collect();
+        cache
+            .entries
+            .retain(|e| !removed_set.contains(e.relative_path.as_str()));
+        for entry in cache.entries.iter_mut() {
+            if let Some(updated) = changed_by_path.get(entry.relative_path.as_str()) {
+                *entry = (*updated).clone();
+            }
+        }
+        cache.entries.extend(added.iter().cloned());
+        cache.entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    }
+
+    Ok(RescanProjectResult {
+        added,
+        removed,
+        changed,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FilterImagesPayload {
+    pub root_path: String,
+    /// Only images whose caption has all of these tags (case-insensitive).
+    #[serde(default)]
+    pub require_tags: Vec<String>,
+    /// Skip images whose caption has any of these tags (case-insensitive).
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+    /// Only images with this rating ("none", "good", "bad", "needs_edit").
+    #[serde(default)]
+    pub rating: Option<String>,
+    #[serde(default)]
+    pub has_caption: Option<bool>,
+    /// Case-insensitive substring matched against the filename and caption.
+    #[serde(default)]
+    pub search: Option<String>,
+}
+
+/// Relative paths of project images matching all given filters, in project order.
+/// Uses the entries cached by open_project; captions edited since are re-read (by mtime) and
+/// ratings are read fresh, so results match what the app currently shows.
+#[tauri::command]
+pub fn filter_images(payload: FilterImagesPayload) -> Result<Vec<String>, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let ratings_data = load_ratings(&payload.root_path);
+
+    let mut cache = PROJECT_CACHE.lock().unwrap();
+    if cache.as_ref().is_none_or(|c| c.root != canonical_root) {
+        let caption_ext = caption_extension(&canonical_root);
+        let mut entries = Vec::new();
+        let ignore = ScanIgnore::default();
+        for entry in WalkDir::new(&canonical_root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| ignore.allows(e))
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if path.is_file() && is_image_path(path) {
+                entries.push(build_image_entry(path, &canonical_root, &caption_ext, &ratings_data, false)?);
+            }
+        }
+        entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        *cache = Some(ProjectCache {
+            root: canonical_root.clone(),
+            caption_ext,
+            entries,
+        });
+    }
+    let Some(cache) = cache.as_mut() else {
+        return Ok(Vec::new());
+    };
+
+    let tag_set = |tags: &[String]| -> HashSet<String> {
+        tags.iter()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect()
+    };
+    let required = tag_set(&payload.require_tags);
+    let excluded = tag_set(&payload.exclude_tags);
+    let rating = payload
+        .rating
+        .as_deref()
+        .map(|r| ImageRating::from_str(r.trim()));
+    let search = payload
+        .search
+        .as_deref()
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty());
+
+    let mut matches = Vec::new();
+    for entry in cache.entries.iter_mut() {
+        // Refresh captions edited since the project was loaded
+        let caption_path = caption_path_for(Path::new(&entry.path), &cache.caption_ext);
+        let caption_modified_ms = fs::metadata(&caption_path).ok().as_ref().and_then(mtime_ms);
+        if caption_modified_ms != entry.caption_modified_ms {
+            let raw = read_caption_text(&caption_path).ok();
+            entry.has_caption = raw.is_some();
+            entry.tags = raw.as_deref().map(parse_tags).unwrap_or_default();
+            entry.caption_modified_ms = caption_modified_ms;
+        }
+
+        if payload.has_caption.is_some_and(|h| h != entry.has_caption) {
+            continue;
+        }
+        if let Some(rating) = rating {
+            let current = ratings_data
+                .ratings
+                .get(&entry.relative_path)
+                .map(|s| ImageRating::from_str(s))
+                .unwrap_or(ImageRating::None);
+            if current != rating {
+                continue;
+            }
+        }
+        if !required.is_empty() || !excluded.is_empty() {
+            let tags: HashSet<String> = entry.tags.iter().map(|t| t.to_lowercase()).collect();
+            if !required.is_subset(&tags) || !excluded.is_disjoint(&tags) {
+                continue;
+            }
+        }
+        if let Some(search) = &search {
+            let in_name = entry.filename.to_lowercase().contains(search.as_str());
+            if !in_name && !entry.tags.join(", ").to_lowercase().contains(search.as_str()) {
+                continue;
+            }
+        }
+        matches.push(entry.relative_path.clone());
+    }
+    Ok(matches)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProjectStatsPayload {
+    pub root_path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolutionCount {
+    pub width: u32,
+    pub height: u32,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectStats {
+    pub image_count: usize,
+    pub captioned_count: usize,
+    pub uncaptioned_count: usize,
+    /// Image count per rating ("good", "bad", "needs_edit", "none").
+    pub rating_counts: HashMap<String, usize>,
+    /// Total size of the image files.
+    pub total_bytes: u64,
+    /// Distinct image resolutions, most common first.
+    pub resolutions: Vec<ResolutionCount>,
+    /// Mean tag count over captioned images.
+    pub average_tags_per_caption: f64,
+}
+
+/// At-a-glance dataset totals in one call. Dimensions come from image headers only.
+#[tauri::command]
+pub fn project_stats(payload: ProjectStatsPayload) -> Result<ProjectStats, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let caption_ext = caption_extension(&canonical_root);
+    let ratings_data = load_ratings(&payload.root_path);
+
+    let mut rating_counts: HashMap<String, usize> = [
+        ImageRating::Good,
+        ImageRating::Bad,
+        ImageRating::NeedsEdit,
+        ImageRating::None,
+    ]
+    .iter()
+    .map(|r| (r.as_str().to_string(), 0))
+    .collect();
+    let mut resolutions: HashMap<(u32, u32), usize> = HashMap::new();
+    let (mut image_count, mut captioned_count, mut total_tags) = (0usize, 0usize, 0usize);
+    let mut total_bytes = 0u64;
+
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical_root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if !path.is_file() || !is_image_path(path) {
+            continue;
+        }
+        let image = build_image_entry(path, &canonical_root, &caption_ext, &ratings_data, true)?;
+        image_count += 1;
+        if image.has_caption {
+            captioned_count += 1;
+            total_tags += image.tags.len();
+        }
+        *rating_counts.entry(image.rating).or_default() += 1;
+        total_bytes += image.file_size.unwrap_or(0);
+        if let (Some(w), Some(h)) = (image.width, image.height) {
+            *resolutions.entry((w, h)).or_default() += 1;
+        }
+    }
+
+    let mut resolutions: Vec<ResolutionCount> = resolutions
+        .into_iter()
+        .map(|((width, height), count)| ResolutionCount {
+            width,
+            height,
+            count,
+        })
+        .collect();
+    resolutions.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then((a.width, a.height).cmp(&(b.width, b.height)))
+    });
+
+    Ok(ProjectStats {
+        image_count,
+        captioned_count,
+        uncaptioned_count: image_count - captioned_count,
+        rating_counts,
+        total_bytes,
+        resolutions,
+        average_tags_per_caption: if captioned_count > 0 {
+            total_tags as f64 / captioned_count as f64
+        } else {
+            0.0
+        },
+    })
+}
+
+const DEFAULT_MIN_SIDE: u32 = 512;
+/// Named aspect ratios (width:height) for the histogram; others fall into "other".
+const ASPECT_BINS: &[(&str, f64)] = &[
+    ("1:1", 1.0),
+    ("4:5", 4.0 / 5.0),
+    ("3:4", 3.0 / 4.0),
+    ("2:3", 2.0 / 3.0),
+    ("9:16", 9.0 / 16.0),
+    ("5:4", 5.0 / 4.0),
+    ("4:3", 4.0 / 3.0),
+    ("3:2", 3.0 / 2.0),
+    ("16:9", 16.0 / 9.0),
+];
+/// Relative difference from a named ratio still counted as that ratio.
+const ASPECT_TOLERANCE: f64 = 0.03;
+/// Upper bounds (exclusive) of the megapixel bins; the last bin is open-ended.
+const MEGAPIXEL_BINS: &[(&str, f64)] = &[
+    ("<0.25", 0.25),
+    ("0.25-0.5", 0.5),
+    ("0.5-1", 1.0),
+    ("1-2", 2.0),
+    ("2-4", 4.0),
+    ("4+", f64::INFINITY),
+];
+
+#[derive(Debug, Deserialize)]
+pub struct AspectHistogramPayload {
+    pub root_path: String,
+    /// Images whose shorter side is below this are counted as low-res (default 512).
+    #[serde(default)]
+    pub min_side: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBin {
+    pub label: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AspectHistogram {
+    /// Named ratios in ASPECT_BINS order, then "other".
+    pub aspect_bins: Vec<HistogramBin>,
+    pub megapixel_bins: Vec<HistogramBin>,
+    pub below_min_side_count: usize,
+    /// Images whose header could not be read.
+    pub unreadable_count: usize,
+}
+
+/// Nearest named aspect ratio within tolerance, or "other".
+fn aspect_label(width: u32, height: u32) -> &'static str {
+    let ratio = width as f64 / height.max(1) as f64;
+    ASPECT_BINS
+        .iter()
+        .map(|&(label, r)| (label, (ratio - r).abs() / r))
+        .filter(|&(_, diff)| diff <= ASPECT_TOLERANCE)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(label, _)| label)
+        .unwrap_or("other")
+}
+
+/// Resolution distribution for bucket planning: images binned by aspect ratio and megapixels,
+/// from header dimensions only.
+#[tauri::command]
+pub fn aspect_histogram(payload: AspectHistogramPayload) -> Result<AspectHistogram, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let min_side = payload.min_side.unwrap_or(DEFAULT_MIN_SIDE);
+
+    let mut aspect_counts = vec![0usize; ASPECT_BINS.len() + 1];
+    let mut megapixel_counts = vec![0usize; MEGAPIXEL_BINS.len()];
+    let (mut below_min_side_count, mut unreadable_count) = (0usize, 0usize);
+
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if !path.is_file() || !is_image_path(path) {
+            continue;
+        }
+        let Some((w, h)) = ImageReader::open(path)
+            .ok()
+            .and_then(|r| r.into_dimensions().ok())
+            .filter(|&(w, h)| w > 0 && h > 0)
+        else {
+            unreadable_count += 1;
+            continue;
+        };
+
+        let label = aspect_label(w, h);
+        let aspect_index = ASPECT_BINS
+            .iter()
+            .position(|(l, _)| *l == label)
+            .unwrap_or(ASPECT_BINS.len());
+        aspect_counts[aspect_index] += 1;
+
+        let megapixels = w as f64 * h as f64 / 1_000_000.0;
+        let mp_index = MEGAPIXEL_BINS
+            .iter()
+            .position(|&(_, upper)| megapixels < upper)
+            .unwrap_or(MEGAPIXEL_BINS.len() - 1);
+        megapixel_counts[mp_index] += 1;
+
+        if w.min(h) < min_side {
+            below_min_side_count += 1;
+        }
+    }
+
+    let aspect_bins = ASPECT_BINS
+        .iter()
+        .map(|(label, _)| *label)
+        .chain(std::iter::once("other"))
+        .zip(aspect_counts)
+        .map(|(label, count)| HistogramBin {
+            label: label.to_string(),
+            count,
+        })
+        .collect();
+    let megapixel_bins = MEGAPIXEL_BINS
+        .iter()
+        .zip(megapixel_counts)
+        .map(|((label, _), count)| HistogramBin {
+            label: label.to_string(),
+            count,
+        })
+        .collect();
+
+    Ok(AspectHistogram {
+        aspect_bins,
+        megapixel_bins,
+        below_min_side_count,
+        unreadable_count,
+    })
+}
+
+/// SHA-256 of a file's contents as hex, or None if it can't be read.
+fn sha256_file(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(_) => return None,
+        }
+    }
+    Some(hex::encode(hasher.finalize()))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct FindDuplicatesPayload {
     pub root_path: String,
+    /// Also compare images in subfolders (default).
+    #[serde(default = "default_true")]
+    pub recursive: bool,
+    /// Folder-name patterns to skip (see ScanIgnore); None skips hidden, originals and
+    /// caption_backups folders. `.lora-studio` is always skipped.
+    #[serde(default)]
+    pub ignore_patterns: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -185,8 +809,13 @@ pub struct FindDuplicatesResult {
 }
 
 /// Find duplicate images by file content hash (SHA-256). Returns groups of relative paths.
+/// Files are first grouped by size; only files sharing a size with another file are hashed.
+/// Emits find-duplicates-progress events ({ processed, total }) every 50 files.
 #[tauri::command]
-pub fn find_duplicates(payload: FindDuplicatesPayload) -> Result<FindDuplicatesResult, String> {
+pub fn find_duplicates(
+    app: AppHandle,
+    payload: FindDuplicatesPayload,
+) -> Result<FindDuplicatesResult, String> {
     let root = PathBuf::from(&payload.root_path);
     if !root.exists() || !root.is_dir() {
         return Err("Folder does not exist".to_string());
@@ -194,49 +823,61 @@ pub fn find_duplicates(payload: FindDuplicatesPayload) -> Result<FindDuplicatesR
     let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
 
     // Collect all image paths first
-    let image_paths: Vec<PathBuf> = WalkDir::new(&root)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|entry| {
-            let path = entry.path();
-            path.is_file() && is_image_path(path)
-        })
-        .map(|entry| entry.path().to_path_buf())
+    let ignore = ScanIgnore::new(payload.ignore_patterns.as_deref());
+    let image_paths = collect_image_paths(&root, payload.recursive, &ignore);
+
+    let total = image_paths.len();
+
+    // Byte-identical files must have the same length, so only hash files whose size is shared
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for path in image_paths {
+        if let Ok(meta) = fs::metadata(&path) {
+            by_size.entry(meta.len()).or_default().push(path);
+        }
+    }
+    let candidates: Vec<PathBuf> = by_size
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .flatten()
         .collect();
 
     // Parallel hash computation
     let hash_to_paths: Mutex<HashMap<String, Vec<String>>> = Mutex::new(HashMap::new());
     
-    image_paths.par_iter().for_each(|path| {
-        // Hash the file
-        if let Ok(mut file) = fs::File::open(path) {
-            let mut hasher = Sha256::new();
-            let mut buf = [0u8; 8192];
-            loop {
-                match file.read(&mut buf) {
-                    Ok(0) => break,
-                    Ok(n) => hasher.update(&buf[..n]),
-                    Err(_) => return,
-                }
-            }
-            let hash_hex = hex::encode(hasher.finalize());
+    // Unique-size files are already settled; progress continues from there as candidates are hashed
+    let processed = AtomicUsize::new(total - candidates.len());
+    let _ = app.emit(
+        FIND_DUPLICATES_PROGRESS_EVENT,
+        ScanProgress { processed: total - candidates.len(), total },
+    );
 
-            // Get relative path
-            let relative = path
-                .strip_prefix(&canonical_root)
-                .unwrap_or(path);
-            let rel_str = relative
-                .to_str()
-                .map(|s| s.replace('\\', "/"))
-                .unwrap_or_default();
-            
-            if !rel_str.is_empty() {
-                let mut map = hash_to_paths.lock().unwrap();
-                map.entry(hash_hex)
-                    .or_default()
-                    .push(rel_str);
-            }
+    candidates.par_iter().for_each(|path| {
+        let hash_hex = sha256_file(path);
+        let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+        if done.is_multiple_of(50) || done == total {
+            let _ = app.emit(
+                FIND_DUPLICATES_PROGRESS_EVENT,
+                ScanProgress { processed: done, total },
+            );
+        }
+        let Some(hash_hex) = hash_hex else {
+            return;
+        };
+
+        // Get relative path
+        let relative = path
+            .strip_prefix(&canonical_root)
+            .unwrap_or(path);
+        let rel_str = relative
+            .to_str()
+            .map(|s| s.replace('\\', "/"))
+            .unwrap_or_default();
+
+        if !rel_str.is_empty() {
+            let mut map = hash_to_paths.lock().unwrap();
+            map.entry(hash_hex)
+                .or_default()
+                .push(rel_str);
         }
     });
 
@@ -249,6 +890,528 @@ pub fn find_duplicates(payload: FindDuplicatesPayload) -> Result<FindDuplicatesR
     Ok(FindDuplicatesResult { groups })
 }
 
+#[derive(Debug, Deserialize)]
+pub struct FindNearDuplicatesPayload {
+    pub root_path: String,
+    /// Max Hamming distance (0-64) between dHashes for two images to count as near-duplicates.
+    #[serde(default = "default_near_duplicate_threshold")]
+    pub threshold: u32,
+}
+
+fn default_near_duplicate_threshold() -> u32 {
+    10
+}
+
+#[derive(Debug, Serialize)]
+pub struct NearDuplicateGroup {
+    pub paths: Vec<String>,
+    /// Largest Hamming distance among the pairs that joined this group (lower = more similar).
+    pub max_distance: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FindNearDuplicatesResult {
+    pub groups: Vec<NearDuplicateGroup>,
+}
+
+/// 64-bit difference hash: downscale to 9x8 grayscale and set one bit per horizontally adjacent
+/// pixel pair where the left pixel is brighter. Robust to re-encoding and resizing.
+fn dhash(path: &Path) -> Option<u64> {
+    let img = image::open(path).ok()?;
+    let small = img.thumbnail_exact(9, 8).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    Some(hash)
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Find visually similar images (re-encoded/resized copies) by perceptual hash.
+/// Emits near-duplicates-progress events while hashing. Groups are sorted most similar first.
+#[tauri::command]
+pub fn find_near_duplicates(
+    app: AppHandle,
+    payload: FindNearDuplicatesPayload,
+) -> Result<FindNearDuplicatesResult, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.exists() || !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let threshold = payload.threshold.min(64);
+
+    let image_paths = collect_image_paths(&canonical_root, true, &ScanIgnore::default());
+
+    let total = image_paths.len();
+    let processed = AtomicUsize::new(0);
+    let hashes: Vec<(String, u64)> = image_paths
+        .par_iter()
+        .filter_map(|path| {
+            let hash = dhash(path);
+            let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            if done.is_multiple_of(50) || done == total {
+                let _ = app.emit(
+                    NEAR_DUPLICATES_PROGRESS_EVENT,
+                    ScanProgress { processed: done, total },
+                );
+            }
+            let rel = path
+                .strip_prefix(&canonical_root)
+                .ok()?
+                .to_str()?
+                .replace('\\', "/");
+            Some((rel, hash?))
+        })
+        .collect();
+
+    // Union images whose hashes are within the threshold, tracking the largest joining distance.
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    let mut max_distance = vec![0u32; hashes.len()];
+    for i in 0..hashes.len() {
+        for j in (i + 1)..hashes.len() {
+            let distance = (hashes[i].1 ^ hashes[j].1).count_ones();
+            if distance > threshold {
+                continue;
+            }
+            let (ri, rj) = (find_root(&mut parent, i), find_root(&mut parent, j));
+            let joined = max_distance[ri].max(max_distance[rj]).max(distance);
+            if ri != rj {
+                parent[rj] = ri;
+            }
+            max_distance[ri] = joined;
+        }
+    }
+
+    let mut by_root: HashMap<usize, Vec<String>> = HashMap::new();
+    for (i, (rel, _)) in hashes.iter().enumerate() {
+        let r = find_root(&mut parent, i);
+        by_root.entry(r).or_default().push(rel.clone());
+    }
+    let mut groups: Vec<NearDuplicateGroup> = by_root
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(r, mut paths)| {
+            paths.sort();
+            NearDuplicateGroup {
+                paths,
+                max_distance: max_distance[r],
+            }
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        a.max_distance
+            .cmp(&b.max_distance)
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+
+    Ok(FindNearDuplicatesResult { groups })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DetectGrayscalePayload {
+    pub root_path: String,
+    /// Images with a saturation score below this (0-1) are flagged. Default 0.04.
+    #[serde(default)]
+    pub threshold: Option<f32>,
+}
+
+const DEFAULT_GRAYSCALE_THRESHOLD: f32 = 0.04;
+/// Longest side images are downscaled to before measuring chroma.
+const GRAYSCALE_SAMPLE_SIZE: u32 = 64;
+
+#[derive(Debug, Serialize)]
+pub struct GrayscaleImage {
+    pub relative_path: String,
+    /// Mean chroma (max - min of R, G, B) over the image, 0 (gray) to 1.
+    pub saturation: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetectGrayscaleResult {
+    /// Flagged images, least saturated first.
+    pub images: Vec<GrayscaleImage>,
+    pub scanned_count: usize,
+}
+
+/// Mean chroma of a downscaled copy of the image; sepia and tinted monochrome score low too.
+fn saturation_score(path: &Path) -> Option<f32> {
+    let img = image::open(path).ok()?;
+    let small = img
+        .thumbnail(GRAYSCALE_SAMPLE_SIZE, GRAYSCALE_SAMPLE_SIZE)
+        .to_rgb8();
+    let pixels = small.pixels().len();
+    if pixels == 0 {
+        return None;
+    }
+    let chroma: u64 = small
+        .pixels()
+        .map(|p| {
+            let [r, g, b] = p.0;
+            (r.max(g).max(b) - r.min(g).min(b)) as u64
+        })
+        .sum();
+    Some(chroma as f32 / (pixels as f32 * 255.0))
+}
+
+/// Find grayscale or nearly colorless images in a project. Emits detect-grayscale-progress events.
+#[tauri::command]
+pub fn detect_grayscale(
+    app: AppHandle,
+    payload: DetectGrayscalePayload,
+) -> Result<DetectGrayscaleResult, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.exists() || !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let threshold = payload.threshold.unwrap_or(DEFAULT_GRAYSCALE_THRESHOLD);
+
+    let image_paths = collect_image_paths(&canonical_root, true, &ScanIgnore::default());
+
+    let total = image_paths.len();
+    let processed = AtomicUsize::new(0);
+    let mut images: Vec<GrayscaleImage> = image_paths
+        .par_iter()
+        .filter_map(|path| {
+            let saturation = saturation_score(path);
+            let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            if done.is_multiple_of(50) || done == total {
+                let _ = app.emit(
+                    DETECT_GRAYSCALE_PROGRESS_EVENT,
+                    ScanProgress { processed: done, total },
+                );
+            }
+            let saturation = saturation.filter(|&s| s < threshold)?;
+            Some(GrayscaleImage {
+                relative_path: relative_to(&canonical_root, path),
+                saturation,
+            })
+        })
+        .collect();
+    images.sort_by(|a, b| {
+        a.saturation
+            .total_cmp(&b.saturation)
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+
+    Ok(DetectGrayscaleResult {
+        images,
+        scanned_count: total,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DetectBlurryPayload {
+    pub root_path: String,
+    /// Images with a sharpness score below this are flagged. Default 100.
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
+const DEFAULT_BLUR_THRESHOLD: f64 = 100.0;
+/// Longest side images are downscaled to before measuring sharpness. Scores depend on scale,
+/// so every image is measured at the same size (smaller images are left as they are).
+const BLUR_SAMPLE_SIZE: u32 = 512;
+
+#[derive(Debug, Serialize)]
+pub struct BlurryImage {
+    pub relative_path: String,
+    /// Variance of the Laplacian of the 0-255 luma; lower is blurrier.
+    pub sharpness: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetectBlurryResult {
+    /// Flagged images, blurriest first.
+    pub images: Vec<BlurryImage>,
+    pub scanned_count: usize,
+}
+
+/// Variance of the 4-neighbour Laplacian over a downscaled grayscale copy of the image.
+fn sharpness_score(path: &Path) -> Option<f64> {
+    let img = image::open(path).ok()?;
+    let gray = img.thumbnail(BLUR_SAMPLE_SIZE, BLUR_SAMPLE_SIZE).to_luma8();
+    let (w, h) = gray.dimensions();
+    if w < 3 || h < 3 {
+        return None;
+    }
+    let px = |x: u32, y: u32| gray.get_pixel(x, y).0[0] as f64;
+    let (mut sum, mut sum_sq) = (0.0, 0.0);
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let lap = px(x - 1, y) + px(x + 1, y) + px(x, y - 1) + px(x, y + 1) - 4.0 * px(x, y);
+            sum += lap;
+            sum_sq += lap * lap;
+        }
+    }
+    let n = ((w - 2) * (h - 2)) as f64;
+    let mean = sum / n;
+    Some(sum_sq / n - mean * mean)
+}
+
+/// Find out-of-focus or motion-blurred images in a project. Emits detect-blurry-progress events.
+#[tauri::command]
+pub fn detect_blurry(
+    app: AppHandle,
+    payload: DetectBlurryPayload,
+) -> Result<DetectBlurryResult, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.exists() || !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let threshold = payload.threshold.unwrap_or(DEFAULT_BLUR_THRESHOLD);
+
+    let image_paths = collect_image_paths(&canonical_root, true, &ScanIgnore::default());
+    let total = image_paths.len();
+    let processed = AtomicUsize::new(0);
+    let mut images: Vec<BlurryImage> = image_paths
+        .par_iter()
+        .filter_map(|path| {
+            let sharpness = sharpness_score(path);
+            let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            if done.is_multiple_of(50) || done == total {
+                let _ = app.emit(
+                    DETECT_BLURRY_PROGRESS_EVENT,
+                    ScanProgress { processed: done, total },
+                );
+            }
+            let sharpness = sharpness.filter(|&s| s < threshold)?;
+            Some(BlurryImage {
+                relative_path: relative_to(&canonical_root, path),
+                sharpness,
+            })
+        })
+        .collect();
+    images.sort_by(|a, b| {
+        a.sharpness
+            .total_cmp(&b.sharpness)
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+
+    Ok(DetectBlurryResult {
+        images,
+        scanned_count: total,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CaptionAuditPayload {
+    pub root_path: String,
+    /// Delete the orphaned caption files after collecting them.
+    #[serde(default)]
+    pub delete_orphans: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CaptionAuditResult {
+    /// Relative paths of caption files with no sibling image.
+    pub orphan_captions: Vec<String>,
+    /// Relative paths of images with no caption file.
+    pub missing_captions: Vec<String>,
+    pub deleted_count: usize,
+}
+
+fn relative_to(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Finds caption files whose image is gone and images without a caption file.
+/// Skips the same folders as open_project (hidden, originals, caption_backups, .lora-studio).
+#[tauri::command]
+pub fn caption_audit(payload: CaptionAuditPayload) -> Result<CaptionAuditResult, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let caption_ext = caption_extension(&canonical_root);
+
+    let mut image_stems: HashSet<PathBuf> = HashSet::new();
+    let mut caption_files: Vec<PathBuf> = Vec::new();
+    let mut missing_captions = Vec::new();
+
+    let ignore = ScanIgnore::default();
+    for entry in WalkDir::new(&canonical_root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        if is_image_path(path) {
+            image_stems.insert(path.with_extension(""));
+            if !caption_path_for(path, &caption_ext).exists() {
+                missing_captions.push(relative_to(&canonical_root, path));
+            }
+        } else if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case(&caption_ext))
+        {
+            caption_files.push(path.to_path_buf());
+        }
+    }
+
+    let orphans: Vec<PathBuf> = caption_files
+        .into_iter()
+        .filter(|p| !image_stems.contains(&p.with_extension("")))
+        .collect();
+
+    let mut deleted_count = 0usize;
+    if payload.delete_orphans {
+        for orphan in &orphans {
+            fs::remove_file(orphan)
+                .map_err(|e| format!("Failed to delete {}: {}", orphan.display(), e))?;
+            deleted_count += 1;
+        }
+    }
+
+    let mut orphan_captions: Vec<String> = orphans
+        .iter()
+        .map(|p| relative_to(&canonical_root, p))
+        .collect();
+    orphan_captions.sort();
+    missing_captions.sort();
+
+    Ok(CaptionAuditResult {
+        orphan_captions,
+        missing_captions,
+        deleted_count,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DetectCaptionAnomaliesPayload {
+    pub root_path: String,
+    /// Don't flag letters outside the Latin script (for non-English datasets).
+    #[serde(default)]
+    pub allow_non_latin: bool,
+    /// Captions shorter than this many characters are flagged (default 8).
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    /// Captions longer than this many characters are flagged (default 1500).
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CaptionAnomaly {
+    pub relative_path: String,
+    pub reasons: Vec<String>,
+}
+
+const DEFAULT_CAPTION_MIN_LENGTH: usize = 8;
+const DEFAULT_CAPTION_MAX_LENGTH: usize = 1500;
+
+/// Letters beyond Latin Extended-B, other than Latin Extended Additional, count as another script.
+fn is_non_latin_letter(c: char) -> bool {
+    c.is_alphabetic() && c > '\u{024F}' && !('\u{1E00}'..='\u{1EFF}').contains(&c)
+}
+
+/// UTF-8 read as Latin-1/Windows-1252 and saved again, e.g. "Ã©" for "é" or "â€™" for "’".
+fn looks_like_mojibake(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(2).any(|w| match w[0] {
+        'Ã' | 'Â' => ('\u{0080}'..='\u{00BF}').contains(&w[1]),
+        'â' => w[1] == '€',
+        _ => false,
+    })
+}
+
+fn caption_anomaly_reasons(text: &str, opt: &DetectCaptionAnomaliesPayload) -> Vec<String> {
+    let mut reasons = Vec::new();
+    let tags = parse_tags(text);
+    if tags.is_empty() {
+        reasons.push("empty caption".to_string());
+        return reasons;
+    }
+    if text.contains('\u{FFFD}') {
+        reasons.push("contains replacement characters".to_string());
+    }
+    if looks_like_mojibake(text) {
+        reasons.push("looks like mis-decoded text (mojibake)".to_string());
+    }
+    if text.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')) {
+        reasons.push("contains control characters".to_string());
+    }
+    if !opt.allow_non_latin {
+        if let Some(tag) = tags.iter().find(|t| t.chars().any(is_non_latin_letter)) {
+            reasons.push(format!("non-Latin script in tag \"{}\"", tag));
+        }
+    }
+    let length = text.trim().chars().count();
+    let min_length = opt.min_length.unwrap_or(DEFAULT_CAPTION_MIN_LENGTH);
+    let max_length = opt.max_length.unwrap_or(DEFAULT_CAPTION_MAX_LENGTH);
+    if length < min_length {
+        reasons.push(format!("too short ({} characters)", length));
+    } else if length > max_length {
+        reasons.push(format!("too long ({} characters)", length));
+    }
+    reasons
+}
+
+/// Flags captions that look like bad AI output: wrong script, replacement characters or
+/// mojibake, invalid UTF-8, or unusual length. Images without a caption file are not listed
+/// (see caption_audit).
+#[tauri::command]
+pub fn detect_caption_anomalies(
+    payload: DetectCaptionAnomaliesPayload,
+) -> Result<Vec<CaptionAnomaly>, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+    let caption_ext = caption_extension(&root);
+
+    let ignore = ScanIgnore::default();
+    let captions: Vec<PathBuf> = WalkDir::new(&root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| ignore.allows(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_image_path(e.path()))
+        .map(|e| caption_path_for(e.path(), &caption_ext))
+        .filter(|p| p.is_file())
+        .collect();
+
+    let mut anomalies: Vec<CaptionAnomaly> = captions
+        .par_iter()
+        .filter_map(|path| {
+            let reasons = match fs::read(path) {
+                Ok(bytes) => match String::from_utf8(bytes) {
+                    Ok(text) => caption_anomaly_reasons(&text, &payload),
+                    Err(_) => vec!["not valid UTF-8".to_string()],
+                },
+                Err(e) => vec![format!("unreadable: {}", e)],
+            };
+            (!reasons.is_empty()).then(|| CaptionAnomaly {
+                relative_path: relative_to(&root, path),
+                reasons,
+            })
+        })
+        .collect();
+    anomalies.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(anomalies)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LoadImageDimensionsPayload {
     pub paths: Vec<String>,
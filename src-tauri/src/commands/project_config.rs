@@ -0,0 +1,241 @@
+//! Per-project settings stored in `.lora-studio/config.json`.
+//! Holds the caption sidecar extension (`txt` by default, e.g. `caption` for trainers that require it),
+//! the opt-in caption backup settings, and the per-dataset values the UI would otherwise ask for
+//! every session (trigger word, default prompt, AI backend, last export options).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::metadata_store;
+
+pub const DEFAULT_CAPTION_EXTENSION: &str = "txt";
+const DEFAULT_MAX_CAPTION_BACKUPS: usize = 10;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// Caption file extension without the leading dot.
+    #[serde(default = "default_caption_extension")]
+    pub caption_extension: String,
+    /// Copy captions to `.lora-studio/caption_backups/` before they are overwritten.
+    #[serde(default)]
+    pub caption_backups: bool,
+    /// Backup versions kept per image.
+    #[serde(default = "default_max_caption_backups")]
+    pub max_caption_backups: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_word: Option<String>,
+    /// Prompt pre-filled for AI captioning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_backend: Option<AiBackendSettings>,
+    /// Export dialog options from the last export, stored as given by the frontend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_export_options: Option<serde_json::Value>,
+}
+
+/// Captioning backend last used for this project.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AiBackendSettings {
+    /// e.g. "lm_studio" or "ollama".
+    #[serde(default)]
+    pub backend: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Caption style the UI was set to (e.g. "tags" or "natural").
+    #[serde(default)]
+    pub caption_mode: Option<String>,
+}
+
+fn default_caption_extension() -> String {
+    DEFAULT_CAPTION_EXTENSION.to_string()
+}
+
+fn default_max_caption_backups() -> usize {
+    DEFAULT_MAX_CAPTION_BACKUPS
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            caption_extension: default_caption_extension(),
+            caption_backups: false,
+            max_caption_backups: default_max_caption_backups(),
+            trigger_word: None,
+            default_prompt: None,
+            ai_backend: None,
+            last_export_options: None,
+        }
+    }
+}
+
+fn config_file_path(root: &Path) -> PathBuf {
+    root.join(".lora-studio").join("config.json")
+}
+
+/// Load the project config, falling back to defaults if missing or invalid.
+pub fn load_project_config(root: &Path) -> ProjectConfig {
+    let mut config: ProjectConfig = fs::read_to_string(config_file_path(root))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    config.caption_extension = normalize_caption_extension(&config.caption_extension)
+        .unwrap_or_else(|_| default_caption_extension());
+    config.max_caption_backups = config.max_caption_backups.max(1);
+    config
+}
+
+/// Nearest ancestor folder of an image that has a `.lora-studio/config.json`.
+pub fn project_root_for_image(image_path: &Path) -> Option<PathBuf> {
+    image_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| config_file_path(dir).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Caption extension configured for the project at `root`.
+pub fn caption_extension(root: &Path) -> String {
+    load_project_config(root).caption_extension
+}
+
+/// Caption extension for an image, taken from the nearest ancestor folder that has a
+/// `.lora-studio/config.json` (so images in subfolders follow the project setting).
+pub fn caption_extension_for_image(image_path: &Path) -> String {
+    project_root_for_image(image_path)
+        .map(|root| caption_extension(&root))
+        .unwrap_or_else(default_caption_extension)
+}
+
+/// Project root and config for an image, as resolved by ProjectLookup.
+pub type ResolvedProject = (PathBuf, Arc<ProjectConfig>);
+
+/// Project settings resolved once per folder, for commands that handle many images: the images
+/// of a folder share the ancestor walk and the `config.json` read instead of repeating them.
+#[derive(Default)]
+pub struct ProjectLookup {
+    folders: Mutex<HashMap<PathBuf, Option<ResolvedProject>>>,
+}
+
+impl ProjectLookup {
+    /// Root and config of the project containing `image_path`, or None if it is not in a project.
+    pub fn project(&self, image_path: &Path) -> Option<ResolvedProject> {
+        let folder = image_path.parent().unwrap_or(Path::new(""));
+        if let Some(found) = self.folders.lock().ok().and_then(|f| f.get(folder).cloned()) {
+            return found;
+        }
+        let found = project_root_for_image(image_path).map(|root| {
+            let config = Arc::new(load_project_config(&root));
+            (root, config)
+        });
+        if let Ok(mut folders) = self.folders.lock() {
+            folders.insert(folder.to_path_buf(), found.clone());
+        }
+        found
+    }
+
+    /// Caption extension for an image, as caption_extension_for_image.
+    pub fn caption_extension(&self, image_path: &Path) -> String {
+        self.project(image_path)
+            .map(|(_, config)| config.caption_extension.clone())
+            .unwrap_or_else(default_caption_extension)
+    }
+
+    /// Caption file path for an image.
+    pub fn caption_path(&self, image_path: &Path) -> PathBuf {
+        image_path.with_extension(self.caption_extension(image_path))
+    }
+}
+
+/// Strip a leading dot and validate: short, alphanumeric, and not an image extension.
+fn normalize_caption_extension(ext: &str) -> Result<String, String> {
+    let ext = ext.trim().trim_start_matches('.').to_lowercase();
+    if ext.is_empty() || ext.len() > 16 || !ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Invalid caption extension: {:?}", ext));
+    }
+    if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        return Err("Caption extension cannot be an image extension".to_string());
+    }
+    Ok(ext)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReadProjectConfigPayload {
+    pub root_path: String,
+}
+
+/// Read the project config (defaults if `.lora-studio/config.json` does not exist).
+#[tauri::command]
+pub fn read_project_config(payload: ReadProjectConfigPayload) -> Result<ProjectConfig, String> {
+    Ok(load_project_config(Path::new(&payload.root_path)))
+}
+
+/// Fields left out keep their current value.
+#[derive(Debug, Deserialize)]
+pub struct WriteProjectConfigPayload {
+    pub root_path: String,
+    #[serde(default)]
+    pub caption_extension: Option<String>,
+    #[serde(default)]
+    pub caption_backups: Option<bool>,
+    #[serde(default)]
+    pub max_caption_backups: Option<usize>,
+    /// An empty string clears the value.
+    #[serde(default)]
+    pub trigger_word: Option<String>,
+    /// An empty string clears the value.
+    #[serde(default)]
+    pub default_prompt: Option<String>,
+    /// Replaces the stored backend settings.
+    #[serde(default)]
+    pub ai_backend: Option<AiBackendSettings>,
+    #[serde(default)]
+    pub last_export_options: Option<serde_json::Value>,
+}
+
+/// Trimmed value, or None for an empty string.
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Update and save the project config. Existing caption files are not renamed.
+#[tauri::command]
+pub fn write_project_config(payload: WriteProjectConfigPayload) -> Result<ProjectConfig, String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+    let mut config = load_project_config(&root);
+    if let Some(ext) = &payload.caption_extension {
+        config.caption_extension = normalize_caption_extension(ext)?;
+    }
+    if let Some(enabled) = payload.caption_backups {
+        config.caption_backups = enabled;
+    }
+    if let Some(max) = payload.max_caption_backups {
+        config.max_caption_backups = max.max(1);
+    }
+    if let Some(trigger_word) = &payload.trigger_word {
+        config.trigger_word = non_empty(trigger_word);
+    }
+    if let Some(prompt) = &payload.default_prompt {
+        config.default_prompt = non_empty(prompt);
+    }
+    if let Some(ai_backend) = payload.ai_backend {
+        config.ai_backend = Some(ai_backend);
+    }
+    if let Some(options) = payload.last_export_options {
+        config.last_export_options = Some(options);
+    }
+    let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    metadata_store::write_atomic(&config_file_path(&root), &content)?;
+    Ok(config)
+}
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
 use std::path::PathBuf;
 
+use super::metadata_store;
+
 /// Image rating status.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
@@ -39,34 +40,32 @@ impl ImageRating {
 pub struct RatingsData {
     /// Map of relative image path -> rating
     pub ratings: HashMap<String, String>,
+    /// Map of relative image path -> star rating (1-5). Missing in older ratings.json files.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub stars: HashMap<String, u8>,
 }
 
+pub const MAX_STARS: u8 = 5;
+
 /// Get the ratings file path for a project root.
 fn ratings_file_path(root: &str) -> PathBuf {
     PathBuf::from(root).join(".lora-studio").join("ratings.json")
 }
 
-/// Load ratings from file.
+/// Load ratings from file (including changes not yet written to disk).
 pub fn load_ratings(root: &str) -> RatingsData {
     let path = ratings_file_path(root);
-    if !path.exists() {
-        return RatingsData::default();
-    }
-    match fs::read_to_string(&path) {
+    match metadata_store::read_to_string(&path) {
         Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
         Err(_) => RatingsData::default(),
     }
 }
 
-/// Save ratings to file.
-fn save_ratings(root: &str, data: &RatingsData) -> Result<(), String> {
-    let path = ratings_file_path(root);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
+/// Save ratings. The file is replaced atomically after a short debounce, so bursts of
+/// rating clicks result in a single write.
+pub fn save_ratings(root: &str, data: &RatingsData) -> Result<(), String> {
     let content = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
-    fs::write(&path, content).map_err(|e| e.to_string())?;
-    Ok(())
+    metadata_store::write_debounced(&ratings_file_path(root), content)
 }
 
 /// Get rating for a specific image.
@@ -114,15 +113,19 @@ pub fn get_ratings(payload: GetRatingsPayload) -> Result<HashMap<String, String>
     Ok(data.ratings)
 }
 
-/// Clear all ratings for a project.
+/// Clear all ratings (including stars) for a project. Returns the number of images that had one.
 #[tauri::command]
 pub fn clear_all_ratings(payload: GetRatingsPayload) -> Result<usize, String> {
-    let path = ratings_file_path(&payload.root_path);
-    if !path.exists() {
+    let data = load_ratings(&payload.root_path);
+    let count = data.ratings.len()
+        + data
+            .stars
+            .keys()
+            .filter(|k| !data.ratings.contains_key(*k))
+            .count();
+    if count == 0 {
         return Ok(0);
     }
-    let data = load_ratings(&payload.root_path);
-    let count = data.ratings.len();
     let empty = RatingsData::default();
     save_ratings(&payload.root_path, &empty)?;
     Ok(count)
@@ -140,12 +143,12 @@ pub struct SetRatingsBatchPayload {
     pub changes: Vec<RatingChange>,
 }
 
-/// Set ratings for multiple images in a single operation (reduces file I/O)
-#[tauri::command]
-pub fn set_ratings_batch(payload: SetRatingsBatchPayload) -> Result<(), String> {
-    let mut data = load_ratings(&payload.root_path);
+/// Load ratings once, apply every change ("none" removes the entry), and save once.
+/// Returns the number of rated images afterwards.
+fn apply_rating_changes(root: &str, changes: &[RatingChange]) -> Result<usize, String> {
+    let mut data = load_ratings(root);
     
-    for change in &payload.changes {
+    for change in changes {
         let rating = ImageRating::from_str(&change.rating);
         if rating == ImageRating::None {
             data.ratings.remove(&change.relative_path);
@@ -154,6 +157,57 @@ pub fn set_ratings_batch(payload: SetRatingsBatchPayload) -> Result<(), String>
         }
     }
     
+    save_ratings(root, &data)?;
+    Ok(data.ratings.len())
+}
+
+/// Set ratings for multiple images in a single operation (reduces file I/O)
+#[tauri::command]
+pub fn set_ratings_batch(payload: SetRatingsBatchPayload) -> Result<(), String> {
+    apply_rating_changes(&payload.root_path, &payload.changes)?;
+    Ok(())
+}
+
+/// Set ratings for many images with a single read and write of ratings.json.
+/// Returns the new total count of rated images.
+#[tauri::command]
+pub fn set_ratings_bulk(payload: SetRatingsBatchPayload) -> Result<usize, String> {
+    apply_rating_changes(&payload.root_path, &payload.changes)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetStarRatingPayload {
+    pub root_path: String,
+    pub relative_path: String,
+    /// 1-5 stars; None or 0 clears the star rating.
+    #[serde(default)]
+    pub stars: Option<u8>,
+}
+
+/// Set (or clear) the star rating for an image. Independent of the good/bad/needs_edit rating.
+#[tauri::command]
+pub fn set_star_rating(payload: SetStarRatingPayload) -> Result<(), String> {
+    let mut data = load_ratings(&payload.root_path);
+
+    match payload.stars.filter(|&s| s > 0) {
+        Some(stars) if stars > MAX_STARS => {
+            return Err(format!("Star rating must be between 1 and {}", MAX_STARS));
+        }
+        Some(stars) => {
+            data.stars.insert(payload.relative_path, stars);
+        }
+        None => {
+            data.stars.remove(&payload.relative_path);
+        }
+    }
+
     save_ratings(&payload.root_path, &data)?;
     Ok(())
 }
+
+/// Get all star ratings for a project (relative path -> 1-5).
+#[tauri::command]
+pub fn get_star_ratings(payload: GetRatingsPayload) -> Result<HashMap<String, u8>, String> {
+    let data = load_ratings(&payload.root_path);
+    Ok(data.stars)
+}
@@ -0,0 +1,85 @@
+//! Folders skipped when scanning a project for images. `.lora-studio` is always skipped; by
+//! default hidden (dot) folders and `originals` / `caption_backups` folders are too.
+
+use std::path::{Component, Path};
+use walkdir::DirEntry;
+
+const METADATA_DIR: &str = ".lora-studio";
+const DEFAULT_IGNORE_PATTERNS: &[&str] = &[".*", "originals", "caption_backups"];
+
+/// Folder-name patterns for WalkDir::filter_entry. Patterns are matched case-insensitively
+/// against each folder's name; `*` matches any run of characters and `?` one character, so
+/// `*backup*` works as a substring match.
+#[derive(Debug, Clone)]
+pub struct ScanIgnore {
+    patterns: Vec<String>,
+}
+
+impl ScanIgnore {
+    /// The given patterns replace the defaults; None keeps them.
+    pub fn new(patterns: Option<&[String]>) -> Self {
+        let patterns = match patterns {
+            Some(patterns) => patterns
+                .iter()
+                .map(|p| p.trim().to_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            None => DEFAULT_IGNORE_PATTERNS.iter().map(|p| p.to_string()).collect(),
+        };
+        Self { patterns }
+    }
+
+    /// Whether the walk should enter/yield this entry. The scan root itself is never skipped.
+    pub fn allows(&self, entry: &DirEntry) -> bool {
+        if entry.depth() == 0 || !entry.file_type().is_dir() {
+            return true;
+        }
+        self.allows_folder(&entry.file_name().to_string_lossy())
+    }
+
+    /// Whether a file at `rel` (relative to the scan root) is outside every skipped folder.
+    pub fn allows_relative(&self, rel: &Path) -> bool {
+        let Some(parent) = rel.parent() else {
+            return true;
+        };
+        parent.components().all(|c| match c {
+            Component::Normal(name) => self.allows_folder(&name.to_string_lossy()),
+            _ => true,
+        })
+    }
+
+    fn allows_folder(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        name != METADATA_DIR && !self.patterns.iter().any(|p| glob_match(p, &name))
+    }
+}
+
+impl Default for ScanIgnore {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and `?` any one character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut pi, mut ni) = (0, 0);
+    // Position after the last `*` in the pattern and the name index it is currently covering.
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi + 1, ni));
+            pi += 1;
+        } else if let Some((after_star, covered)) = star {
+            pi = after_star;
+            ni = covered + 1;
+            star = Some((after_star, covered + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
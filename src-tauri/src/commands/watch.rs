@@ -0,0 +1,128 @@
+//! Watch a project folder and emit `project-file-changed` events when images or captions are
+//! added, removed or modified outside the app (external editors, captioning scripts).
+
+use notify_debouncer_full::notify::event::{EventKind, ModifyKind};
+use notify_debouncer_full::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::project_config::caption_extension;
+use super::scan_filter::ScanIgnore;
+
+const FILE_CHANGED_EVENT: &str = "project-file-changed";
+/// Rapid writes to the same file within this window are reported once.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+type ProjectWatcher = Debouncer<RecommendedWatcher, RecommendedCache>;
+
+/// The active watcher; only one project is watched at a time.
+static WATCHER: Lazy<Mutex<Option<ProjectWatcher>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectFileChanged {
+    pub relative_path: String,
+    /// "added", "removed" or "modified".
+    pub kind: String,
+}
+
+/// Image or caption file (by extension), outside the folders open_project skips.
+fn is_watched_file(root: &Path, path: &Path, caption_ext: &str) -> bool {
+    let Ok(rel) = path.strip_prefix(root) else {
+        return false;
+    };
+    if !ScanIgnore::default().allows_relative(rel) {
+        return false;
+    }
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .is_some_and(|e| e == caption_ext || IMAGE_EXTENSIONS.contains(&e.as_str()))
+}
+
+fn change_kind(kind: &EventKind, path: &Path) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("added"),
+        EventKind::Remove(_) => Some("removed"),
+        // Renames arrive as name changes; whether the path still exists tells which side it is.
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            Some(if path.exists() { "added" } else { "removed" })
+        }
+        EventKind::Modify(_) => Some("modified"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WatchProjectPayload {
+    pub root_path: String,
+}
+
+/// Start watching a project folder recursively, replacing any previous watcher.
+#[tauri::command]
+pub fn watch_project(app: AppHandle, payload: WatchProjectPayload) -> Result<(), String> {
+    let root = PathBuf::from(&payload.root_path);
+    if !root.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+    let root = root.canonicalize().map_err(|e| e.to_string())?;
+    let caption_ext = caption_extension(&root);
+
+    let event_root = root.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+        let Ok(events) = result else {
+            return;
+        };
+        for event in events {
+            for path in &event.paths {
+                if !is_watched_file(&event_root, path, &caption_ext) {
+                    continue;
+                }
+                let Some(kind) = change_kind(&event.kind, path) else {
+                    continue;
+                };
+                let relative_path = path
+                    .strip_prefix(&event_root)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let _ = app.emit(
+                    FILE_CHANGED_EVENT,
+                    ProjectFileChanged {
+                        relative_path,
+                        kind: kind.to_string(),
+                    },
+                );
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to start watcher: {}", e))?;
+    debouncer
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch folder: {}", e))?;
+
+    let previous = WATCHER.lock().unwrap().replace(debouncer);
+    if let Some(previous) = previous {
+        previous.stop();
+    }
+    Ok(())
+}
+
+/// Stop the active project watcher. Returns false if nothing was being watched.
+#[tauri::command]
+pub fn stop_watching() -> Result<bool, String> {
+    let watcher = WATCHER.lock().unwrap().take();
+    Ok(match watcher {
+        Some(watcher) => {
+            watcher.stop();
+            true
+        }
+        None => false,
+    })
+}
@@ -7,40 +7,93 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .manage(commands::ai_concurrency::AiConcurrencyLimiter::default())
         .invoke_handler(tauri::generate_handler![
             commands::project::open_project,
+            commands::project::rescan_project,
+            commands::project::filter_images,
+            commands::project::project_stats,
+            commands::project::aspect_histogram,
+            commands::disk::disk_stats,
+            commands::watch::watch_project,
+            commands::watch::stop_watching,
             commands::project::find_duplicates,
+            commands::project::find_near_duplicates,
+            commands::project::detect_grayscale,
+            commands::project::detect_blurry,
+            commands::project::detect_caption_anomalies,
+            commands::project::caption_audit,
+            commands::project_config::read_project_config,
+            commands::project_config::write_project_config,
             commands::project::load_image_dimensions,
             commands::images::get_thumbnail,
             commands::images::get_thumbnails_batch,
+            commands::images::clear_thumbnail_cache,
+            commands::images::set_thumbnail_cache_limit,
+            commands::images::thumbnail_cache_stats,
+            commands::images::prewarm_thumbnails,
             commands::images::get_image_data_url,
+            commands::images::get_image_preview,
             commands::images::crop_image,
+            commands::images::restore_original,
+            commands::images::trim_borders,
             commands::images::multi_crop,
             commands::images::batch_resize,
+            commands::images::convert_images,
             commands::images::delete_image,
             commands::captions::read_caption,
             commands::captions::get_captions_batch,
             commands::captions::write_caption,
+            commands::captions::write_caption_merged,
             commands::captions::add_tag,
             commands::captions::remove_tag,
             commands::captions::reorder_tags,
             commands::captions::clear_all_captions,
+            commands::captions::replace_in_captions,
+            commands::captions::rename_tag,
+            commands::captions::apply_tag_aliases,
+            commands::captions::tag_frequency,
+            commands::captions::tag_vocabulary,
+            commands::captions::caption_stats,
+            commands::captions::import_captions,
+            commands::captions::normalize_caption_encoding,
+            commands::captions::preview_caption_changes,
+            commands::caption_backups::list_caption_backups,
+            commands::caption_backups::restore_caption_backup,
             commands::lm_studio::test_lm_studio_connection,
             commands::lm_studio::generate_caption_lm_studio,
             commands::lm_studio::generate_captions_batch,
+            commands::lm_studio::cancel_caption_batch,
+            commands::ai_concurrency::get_ai_concurrency,
+            commands::ai_concurrency::set_ai_concurrency,
             commands::ollama::test_ollama_connection,
+            commands::ollama::generate_caption_ollama,
+            commands::ollama::generate_captions_ollama_batch,
             commands::export::export_dataset,
             commands::export::export_by_rating,
+            commands::export::export_ratings_report,
             commands::ratings::set_rating,
             commands::ratings::set_ratings_batch,
+            commands::ratings::set_ratings_bulk,
             commands::ratings::get_ratings,
             commands::ratings::clear_all_ratings,
+            commands::ratings::set_star_rating,
+            commands::ratings::get_star_ratings,
             commands::crop_status::set_crop_status,
             commands::crop_status::get_crop_statuses,
+            commands::crop_status::crop_status_counts,
             commands::crop_status::clear_all_crop_statuses,
             commands::batch_rename::batch_rename,
+            commands::batch_rename::undo_last_rename,
+            commands::batch_rename::move_images,
             commands::detect::detect_faces,
+            commands::images::autocrop_to_face,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running LoRA Dataset Studio");
+        .build(tauri::generate_context!())
+        .expect("error while running LoRA Dataset Studio")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                commands::metadata_store::flush_pending_writes();
+            }
+        });
 }
@@ -20,7 +20,9 @@ import {
   testOllamaConnection,
   generateCaptionLmStudio,
   generateCaptionsBatch,
+  cancelCaptionBatch,
   writeCaption,
+  errorMessage,
 } from "@/lib/tauri";
 import { buildEffectivePrompt } from "@/lib/promptBuilder";
 
@@ -30,6 +32,7 @@ export function AiPanel() {
   const [templateName, setTemplateName] = useState("");
   const [templateSearch, setTemplateSearch] = useState("");
   const cancelBatchRef = useRef(false);
+  const batchIdRef = useRef<string | null>(null);
 
   const queryClient = useQueryClient();
   const rootPath = useProjectStore((s) => s.rootPath);
@@ -149,8 +152,8 @@ export function AiPanel() {
         showToast(result.error);
       }
     },
-    onError: (err: Error) => {
-      showToast(err.message);
+    onError: (err) => {
+      showToast(errorMessage(err));
     },
   });
 
@@ -191,6 +194,7 @@ export function AiPanel() {
     setIsGenerating(true);
     setGenerationProgress(0, targetImages.length);
     cancelBatchRef.current = false;
+    batchIdRef.current = `captions-${Date.now()}`;
 
     try {
       const baseUrl = provider === "ollama" ? ollama.base_url : lmStudio.base_url;
@@ -205,7 +209,7 @@ export function AiPanel() {
 
         const timeoutSecs = lmStudio.timeout_secs ?? 120;
         const maxImageDimension = lmStudio.max_image_dimension ?? null;
-        const results = await generateCaptionsBatch(
+        const { results, canceled } = await generateCaptionsBatch(
           paths,
           baseUrl,
           model,
@@ -213,12 +217,14 @@ export function AiPanel() {
           300,
           timeoutSecs,
           batchConcurrency,
-          maxImageDimension
+          maxImageDimension,
+          batchIdRef.current
         );
 
         let failed = 0;
         let firstError: string | null = null;
         for (const result of results) {
+          if (result.skipped || result.written_path) continue;
           if (result.success && result.caption) {
             const tags = result.caption
               .split(",")
@@ -237,12 +243,14 @@ export function AiPanel() {
           );
         }
 
+        if (canceled) break;
         setGenerationProgress(Math.min(i + chunkSize, targetImages.length), targetImages.length);
       }
     } catch (err) {
-      showToast(String(err instanceof Error ? err.message : err));
+      showToast(errorMessage(err));
     } finally {
       cancelBatchRef.current = false;
+      batchIdRef.current = null;
       setIsGenerating(false);
       if (rootPath) {
         queryClient.invalidateQueries({ queryKey: ["project", "images", rootPath] });
@@ -252,6 +260,9 @@ export function AiPanel() {
 
   function handleStopCaptioning() {
     cancelBatchRef.current = true;
+    if (batchIdRef.current) {
+      cancelCaptionBatch(batchIdRef.current).catch(() => {});
+    }
   }
 
   const uncaptionedCount = useMemo(
@@ -12,6 +12,7 @@ import {
   exportByRating,
   selectSaveFolder,
   selectSaveFile,
+  errorMessage,
 } from "@/lib/tauri";
 import type { ExportResult } from "@/types";
 
@@ -96,8 +97,8 @@ export function ExportModal({ isOpen, onClose }: ExportModalProps) {
       });
     },
     onSuccess: (res) => setResult(res),
-    onError: (err: Error) => {
-      const msg = err.message ?? String(err);
+    onError: (err) => {
+      const msg = errorMessage(err);
       setResult({
         success: false,
         exported_count: 0,
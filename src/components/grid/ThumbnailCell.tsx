@@ -7,6 +7,8 @@ import {
   setImageRating,
   deleteImage,
   generateCaptionLmStudio,
+  errorMessage,
+  isCommandError,
 } from "@/lib/tauri";
 import { buildEffectivePrompt } from "@/lib/promptBuilder";
 import { useAiStore } from "@/stores/aiStore";
@@ -181,6 +183,8 @@ export const ThumbnailCell = memo(function ThumbnailCell({ entry, size, index, i
   const [captionText, setCaptionText] = useState(() => tagsToText(entry.tags));
   const [isEditing, setIsEditing] = useState(false);
   const [showDeleteConfirm, setShowDeleteConfirm] = useState(false);
+  /** Set when moving to the trash failed; the modal then offers a permanent delete instead. */
+  const [trashError, setTrashError] = useState<string | null>(null);
   const [showClearTagsConfirm, setShowClearTagsConfirm] = useState(false);
   const captionInputRef = useRef<HTMLTextAreaElement>(null);
   const deleteModalRef = useRef<HTMLDivElement>(null);
@@ -237,10 +241,21 @@ export const ThumbnailCell = memo(function ThumbnailCell({ entry, size, index, i
         }
       }
     },
+    onError: (err) => {
+      showToast(errorMessage(err));
+    },
   });
 
   const deleteMutation = useMutation({
-    mutationFn: () => deleteImage(entry.path),
+    mutationFn: (permanent: boolean) => deleteImage(entry.path, permanent),
+    onError: (err) => {
+      if (isCommandError(err) && err.code === "trash_failed") {
+        setTrashError(err.message);
+        setShowDeleteConfirm(true);
+      } else {
+        showToast(errorMessage(err));
+      }
+    },
     onSuccess: () => {
       if (selectedImage?.id === entry.id) {
         setSelectedImage(null);
@@ -284,8 +299,8 @@ export const ThumbnailCell = memo(function ThumbnailCell({ entry, size, index, i
         showToast(result.error);
       }
     },
-    onError: (err: Error) => {
-      showToast(err.message);
+    onError: (err) => {
+      showToast(errorMessage(err));
     },
   });
 
@@ -397,11 +412,13 @@ export const ThumbnailCell = memo(function ThumbnailCell({ entry, size, index, i
 
   function handleDeleteConfirm() {
     setShowDeleteConfirm(false);
-    deleteMutation.mutate();
+    deleteMutation.mutate(trashError !== null);
+    setTrashError(null);
   }
 
   function handleDeleteCancel() {
     setShowDeleteConfirm(false);
+    setTrashError(null);
   }
 
   function doClearTags() {
@@ -628,7 +645,7 @@ export const ThumbnailCell = memo(function ThumbnailCell({ entry, size, index, i
                 className="flex items-center gap-2 text-lg font-medium text-gray-100"
               >
                 <Trash2 className="h-5 w-5 text-red-400" />
-                Delete image?
+                {trashError ? "Delete permanently?" : "Delete image?"}
               </h2>
               <button
                 type="button"
@@ -640,10 +657,17 @@ export const ThumbnailCell = memo(function ThumbnailCell({ entry, size, index, i
               </button>
             </div>
             <div className="space-y-4 p-4">
-              <p className="text-sm text-gray-400">
-                Are you sure you want to delete this image from the folder? The
-                file and its caption will be removed. This cannot be undone.
-              </p>
+              {trashError ? (
+                <p className="text-sm text-gray-400">
+                  {trashError}. Delete the image and its caption permanently
+                  instead? This cannot be undone.
+                </p>
+              ) : (
+                <p className="text-sm text-gray-400">
+                  Are you sure you want to delete this image from the folder? The
+                  file and its caption will be moved to the trash.
+                </p>
+              )}
               <div className="flex gap-2">
                 <button
                   type="button"
@@ -663,7 +687,7 @@ export const ThumbnailCell = memo(function ThumbnailCell({ entry, size, index, i
                   ) : (
                     <Trash2 className="h-4 w-4" />
                   )}
-                  Delete
+                  {trashError ? "Delete permanently" : "Delete"}
                 </button>
               </div>
             </div>
@@ -17,7 +17,7 @@ import { useProjectStore } from "@/stores/projectStore";
 import { useUiStore } from "@/stores/uiStore";
 import { useCropStore } from "@/stores/cropStore";
 import { useFocusTrap } from "@/hooks/useFocusTrap";
-import { cropImage, getImageDataUrl, detectFaces, multiCrop, setCropStatus } from "@/lib/tauri";
+import { cropImage, getImageDataUrl, detectFaces, multiCrop, setCropStatus, errorMessage } from "@/lib/tauri";
 import type { CropRect } from "@/lib/tauri";
 import { computeBuckets, BUILTIN_PROFILES } from "@/lib/buckets";
 import type { FaceRegion } from "@/types";
@@ -1070,16 +1070,12 @@ export function CropModal() {
             </button>
             {cropMutation.isError && (
               <p className="text-xs text-red-400" role="alert">
-                {cropMutation.error instanceof Error
-                  ? cropMutation.error.message
-                  : String(cropMutation.error)}
+                {errorMessage(cropMutation.error)}
               </p>
             )}
             {multiCropMutation.isError && (
               <p className="text-xs text-red-400" role="alert">
-                {multiCropMutation.error instanceof Error
-                  ? multiCropMutation.error.message
-                  : String(multiCropMutation.error)}
+                {errorMessage(multiCropMutation.error)}
               </p>
             )}
           </div>
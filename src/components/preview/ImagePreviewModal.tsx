@@ -6,7 +6,7 @@ import { useProjectImages } from "@/hooks/useProject";
 import { useProjectStore } from "@/stores/projectStore";
 import { useUiStore } from "@/stores/uiStore";
 import { useFocusTrap } from "@/hooks/useFocusTrap";
-import { getImageDataUrl, setImageRating } from "@/lib/tauri";
+import { getImageDataUrl, setImageRating, errorMessage } from "@/lib/tauri";
 import type { ImageRating } from "@/types";
 
 interface ImagePreviewModalProps {
@@ -22,6 +22,7 @@ export function ImagePreviewModal({ isOpen, onClose }: ImagePreviewModalProps) {
   const { data: images = [] } = useProjectImages();
   const rootPath = useProjectStore((s) => s.rootPath);
   const openCrop = useUiStore((s) => s.openCrop);
+  const showToast = useUiStore((s) => s.showToast);
   const queryClient = useQueryClient();
 
   const [zoom, setZoom] = useState(1);
@@ -43,6 +44,9 @@ export function ImagePreviewModal({ isOpen, onClose }: ImagePreviewModalProps) {
         setSelectedImage({ ...selectedImage, rating: newRating });
       }
     },
+    onError: (err) => {
+      showToast(errorMessage(err));
+    },
   });
 
   function handleRatingClick(rating: ImageRating) {
@@ -2,7 +2,7 @@ import { useState } from "react";
 import { useMutation } from "@tanstack/react-query";
 import { X, Copy, Loader2 } from "lucide-react";
 import { useProjectStore } from "@/stores/projectStore";
-import { findDuplicates } from "@/lib/tauri";
+import { findDuplicates, errorMessage } from "@/lib/tauri";
 
 interface FindDuplicatesModalProps {
   isOpen: boolean;
@@ -23,8 +23,8 @@ export function FindDuplicatesModal({ isOpen, onClose }: FindDuplicatesModalProp
       setResult(res);
       setError(null);
     },
-    onError: (err: Error) => {
-      setError(err.message ?? "Failed to find duplicates");
+    onError: (err) => {
+      setError(errorMessage(err) || "Failed to find duplicates");
       setResult(null);
     },
   });
@@ -1,12 +1,14 @@
 import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
 import type {
+  CommandError,
   ImageEntry,
+  ProjectConfig,
   ImageRating,
   CaptionData,
   ConnectionStatus,
   CaptionResult,
-  BatchCaptionResult,
+  BatchCaptionResponse,
   ExportOptions,
   ExportResult,
   ExportByRatingOptions,
@@ -20,12 +22,31 @@ import type {
  * - Commands that expect { payload: { ... } }: open_project, get_thumbnail, get_image_data_url,
  *   crop_image, read_caption, write_caption, add_tag, remove_tag, reorder_tags,
  *   test_lm_studio_connection, test_ollama_connection, generate_caption_lm_studio, generate_captions_batch,
- *   clear_all_ratings, set_rating, get_ratings, batch_rename.
+ *   cancel_caption_batch, clear_all_ratings, set_rating, get_ratings, batch_rename.
  * - Commands that expect the args object directly (no "payload" key): find_duplicates, delete_image,
  *   batch_resize, export_dataset, export_by_rating.
  * - No args: get_resource_stats.
  */
 
+/** True for the `{ code, message }` errors rejected by image, caption and export commands. */
+export function isCommandError(err: unknown): err is CommandError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as CommandError).code === "string" &&
+    typeof (err as CommandError).message === "string"
+  );
+}
+
+/**
+ * Message to show for a rejected invoke: commands reject with a CommandError, a plain string
+ * (commands still returning `Result<_, String>`) or, for frontend failures, an Error.
+ */
+export function errorMessage(err: unknown): string {
+  if (err instanceof Error || isCommandError(err)) return err.message;
+  return String(err);
+}
+
 export async function openFolder(): Promise<string | null> {
   const selected = await open({
     directory: true,
@@ -35,8 +56,22 @@ export async function openFolder(): Promise<string | null> {
   return selected;
 }
 
+export interface OpenProjectResult {
+  entries: ImageEntry[];
+  config: ProjectConfig;
+}
+
+/** Opens a project and returns its image entries (the project config is in openProject's result). */
 export async function loadProject(rootPath: string, includeDimensions = false): Promise<ImageEntry[]> {
-  return invoke<ImageEntry[]>("open_project", {
+  const result = await openProject(rootPath, includeDimensions);
+  return result.entries;
+}
+
+export async function openProject(
+  rootPath: string,
+  includeDimensions = false
+): Promise<OpenProjectResult> {
+  return invoke<OpenProjectResult>("open_project", {
     payload: { root_path: rootPath, include_dimensions: includeDimensions },
   });
 }
@@ -117,9 +152,17 @@ export async function cropImage(
   return invoke<string | undefined>("crop_image", { payload });
 }
 
-/** Deletes an image file and its caption .txt from disk. */
-export async function deleteImage(imagePath: string): Promise<void> {
-  return invoke<void>("delete_image", { image_path: imagePath });
+export interface DeleteImageResult {
+  /** True if the image and caption went to the OS trash; false if permanently removed. */
+  trashed: boolean;
+}
+
+/** Deletes an image file and its caption .txt (to the OS trash unless permanent is true). */
+export async function deleteImage(
+  imagePath: string,
+  permanent = false
+): Promise<DeleteImageResult> {
+  return invoke<DeleteImageResult>("delete_image", { image_path: imagePath, permanent });
 }
 
 export type BatchResizeMode = "resize" | "center_crop" | "fit";
@@ -242,9 +285,10 @@ export async function generateCaptionsBatch(
   maxTokens: number = 300,
   timeoutSecs: number = 120,
   concurrency: number = 1,
-  maxImageDimension: number | null = null
-): Promise<BatchCaptionResult[]> {
-  return invoke<BatchCaptionResult[]>("generate_captions_batch", {
+  maxImageDimension: number | null = null,
+  batchId: string = ""
+): Promise<BatchCaptionResponse> {
+  return invoke<BatchCaptionResponse>("generate_captions_batch", {
     payload: {
       image_paths: imagePaths,
       base_url: baseUrl,
@@ -254,10 +298,18 @@ export async function generateCaptionsBatch(
       timeout_secs: timeoutSecs,
       max_image_dimension: maxImageDimension ?? undefined,
       concurrency,
+      batch_id: batchId,
     },
   });
 }
 
+/** Stop a running caption batch. Returns false if no batch with that id is running. */
+export async function cancelCaptionBatch(batchId: string): Promise<boolean> {
+  return invoke<boolean>("cancel_caption_batch", {
+    payload: { batch_id: batchId },
+  });
+}
+
 // ============ Export Functions ============
 
 export async function exportDataset(
@@ -366,7 +418,14 @@ export async function multiCrop(payload: MultiCropPayload): Promise<string[]> {
 
 // ============ Crop Status Tracking ============
 
-export type CropStatus = "uncropped" | "cropped" | "multi" | "flagged";
+export type CropStatus =
+  | "uncropped"
+  | "cropped"
+  | "multi"
+  | "has_crop"
+  | "flagged"
+  | "needs_recrop"
+  | "skipped";
 
 export async function setCropStatus(
   rootPath: string,
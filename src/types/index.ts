@@ -2,7 +2,49 @@
 export type ImageRating = "none" | "good" | "bad" | "needs_edit";
 
 /** Crop status for dataset preparation tracking. */
-export type CropStatus = "uncropped" | "cropped" | "multi" | "flagged";
+export type CropStatus =
+  | "uncropped"
+  | "cropped"
+  | "multi"
+  | "has_crop"
+  | "flagged"
+  | "needs_recrop"
+  | "skipped";
+
+/** Error returned by image, caption and export commands. */
+export interface CommandError {
+  code:
+    | "not_found"
+    | "permission_denied"
+    | "already_exists"
+    | "invalid_input"
+    | "decode"
+    | "encode"
+    | "unsupported"
+    | "trash_failed"
+    | "io"
+    | "other";
+  message: string;
+}
+
+/** Captioning backend last used for a project. */
+export interface AiBackendSettings {
+  backend?: string | null;
+  base_url?: string | null;
+  model?: string | null;
+  caption_mode?: string | null;
+}
+
+/** Per-project settings from .lora-studio/config.json (read_project_config / write_project_config). */
+export interface ProjectConfig {
+  caption_extension: string;
+  caption_backups: boolean;
+  max_caption_backups: number;
+  trigger_word?: string;
+  default_prompt?: string;
+  ai_backend?: AiBackendSettings;
+  last_export_options?: Record<string, unknown>;
+}
 
 /** Face detection region. */
 export interface FaceRegion {
@@ -89,6 +131,16 @@ export interface BatchCaptionResult {
   success: boolean;
   caption: string;
   error: string | null;
+  /** Left alone because it already had a caption (skip_existing). */
+  skipped: boolean;
+  /** Caption file the batch already wrote (when called with write). */
+  written_path: string | null;
+}
+
+export interface BatchCaptionResponse {
+  results: BatchCaptionResult[];
+  canceled: boolean;
+  skipped_count: number;
 }
 
 /** LM Studio settings. */
@@ -196,6 +248,15 @@ export interface BatchRenameOptions {
   prefix: string;
   start_index: number;
   zero_pad: number;
+  /** Preview only: compute mappings and collisions without renaming. */
+  dry_run?: boolean;
+  /** Tokens: {prefix}, {index}, {orig}, {parent}, {ext}. Defaults to "{prefix}_{index}". */
+  pattern?: string;
+}
+
+export interface RenameMapping {
+  old_relative: string;
+  new_relative: string;
 }
 
 /** Batch rename result. */
@@ -203,6 +264,7 @@ export interface BatchRenameResult {
   success: boolean;
   renamed_count: number;
   errors: string[];
+  mappings: RenameMapping[];
 }
 
 export const DEFAULT_PROMPT_TEMPLATES: PromptTemplate[] = [